@@ -25,8 +25,12 @@ fn main() -> ! {
     let p = hal::Peripherals::take().unwrap();
 
     let pins = p.pins;
+    let clocks = p.clock.freeze();
 
-    let mut spi = p
+    // Send data over SPI at up to 10MHz, the SSD1322's maximum rate. The
+    // divider is calculated from the system clock rate, so this remains
+    // correct if the clock configuration above changes.
+    let spi = p
         .spi0
         .activate_as_host(
             pins.gpio12,
@@ -34,6 +38,7 @@ fn main() -> ! {
                 sclk_mode: embedded_hal::spi::MODE_0,
                 bit_order: hal::spi::cfg::BitOrder::MSBFirst,
             },
+            Some((10_000_000, &clocks)),
         )
         .with_mosi(pins.gpio14);
     let cs = pins.gpio13.to_digital_output(true);
@@ -48,11 +53,6 @@ fn main() -> ! {
         rst.set_high().unwrap();
     }
 
-    // Send data over SPI at our maximum rate (divide clock by 1).
-    // If the main system clock speed were set faster than the default then
-    // this would need to be increased.
-    spi.set_clock_divider(1);
-
     let mut driver = SSD1322::new(spi, cs, dc);
     init(&mut driver).unwrap();
 
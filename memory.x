@@ -1,5 +1,13 @@
 MEMORY
 {
-  FLASH : ORIGIN = 0x00000000, LENGTH = 16K
+  FLASH : ORIGIN = 0x00000000, LENGTH = 14K
+  /* Reserved for `flash::eeprom::Store`. Must be at least two 1K sectors. */
+  EEPROM : ORIGIN = 0x00003800, LENGTH = 2K
   RAM : ORIGIN = 0x10000000, LENGTH = 4K
 }
+
+/* The IAP routines in the boot ROM use the top 32 bytes of RAM. */
+_stack_start = ORIGIN(RAM) + LENGTH(RAM) - 32;
+
+_eeprom_start = ORIGIN(EEPROM);
+_eeprom_end = ORIGIN(EEPROM) + LENGTH(EEPROM);
@@ -0,0 +1,201 @@
+//! Interface to the brown-out detector (BOD).
+//!
+//! The brown-out detector monitors the supply voltage and can raise an
+//! interrupt when it drops below one threshold and reset the device when it
+//! drops below another, lower threshold. A typical use is to save state when
+//! the interrupt fires, relying on the reset to stop the program before the
+//! supply drops too low for reliable operation.
+//!
+//! ```rust
+//! let mut bod = p.bod;
+//! bod.set_interrupt_level(hal::bod::InterruptLevel::Level3);
+//! bod.set_reset_level(hal::bod::ResetLevel::Level2);
+//! bod.enable_reset();
+//! bod.enable_interrupt();
+//! ```
+//!
+//! Whether the most recent reset was caused by the brown-out detector can be
+//! determined using `BOD::caused_reset` or `Peripherals::reset_cause`.
+
+use crate::wakeup;
+use core::marker::PhantomData;
+
+/// Represents the brown-out detector.
+pub struct BOD(PhantomData<()>);
+
+/// The BOD object represents access to a single system peripheral, so it's
+/// not safe to share it across multiple threads without some external
+/// concurrency control mechanisms.
+impl !Sync for BOD {}
+
+impl BOD {
+    pub const NVIC_BITMASK: u32 = 1 << 13;
+
+    pub(crate) fn new() -> Self {
+        Self(PhantomData)
+    }
+
+    // The brown-out detector is powered at reset, but we power it explicitly
+    // anyway in case the application has used the PAC to turn it off.
+    fn power_up() {
+        let syscon = lpc81x_pac::SYSCON::ptr();
+        unsafe { (*syscon).pdruncfg.modify(|_, w| w.bod_pd().powered()) }
+    }
+
+    /// Selects the supply voltage below which the device will be reset, if
+    /// the BOD reset is enabled.
+    pub fn set_reset_level(&mut self, level: ResetLevel) {
+        let syscon = lpc81x_pac::SYSCON::ptr();
+        unsafe {
+            (*syscon).bodctrl.modify(|_, w| match level {
+                ResetLevel::Level1 => w.bodrstlev().level_1_the_reset_a(),
+                ResetLevel::Level2 => w.bodrstlev().level_2_the_reset_a(),
+                ResetLevel::Level3 => w.bodrstlev().level_3_the_reset_a(),
+            })
+        }
+    }
+
+    /// Selects the supply voltage below which the BOD interrupt will be
+    /// requested, if the interrupt is enabled.
+    pub fn set_interrupt_level(&mut self, level: InterruptLevel) {
+        let syscon = lpc81x_pac::SYSCON::ptr();
+        unsafe {
+            (*syscon).bodctrl.modify(|_, w| match level {
+                InterruptLevel::Level1 => w.bodintval().level_1the_interrup(),
+                InterruptLevel::Level2 => w.bodintval().level_2_the_interru(),
+                InterruptLevel::Level3 => w.bodintval().level_3_the_interru(),
+            })
+        }
+    }
+
+    /// Enables the BOD reset, so that the device will reset if the supply
+    /// voltage drops below the level selected by `set_reset_level`.
+    pub fn enable_reset(&mut self) {
+        Self::power_up();
+        let syscon = lpc81x_pac::SYSCON::ptr();
+        unsafe {
+            (*syscon)
+                .bodctrl
+                .modify(|_, w| w.bodrstena().enable_reset_functio())
+        }
+    }
+
+    /// Disables the BOD reset.
+    pub fn disable_reset(&mut self) {
+        let syscon = lpc81x_pac::SYSCON::ptr();
+        unsafe {
+            (*syscon)
+                .bodctrl
+                .modify(|_, w| w.bodrstena().disable_reset_functi())
+        }
+    }
+
+    /// Enables the BOD interrupt, which will be requested while the supply
+    /// voltage is below the level selected by `set_interrupt_level`.
+    #[inline(always)]
+    pub fn enable_interrupt(&self) {
+        Self::power_up();
+        let nvic = lpc81x_pac::NVIC::ptr();
+        unsafe {
+            (*nvic).iser[0].write(Self::NVIC_BITMASK);
+        }
+    }
+
+    /// Disables the BOD interrupt.
+    #[inline(always)]
+    pub fn disable_interrupt(&self) {
+        let nvic = lpc81x_pac::NVIC::ptr();
+        unsafe {
+            (*nvic).icer[0].write(Self::NVIC_BITMASK);
+        }
+    }
+
+    /// Clears the pending state of the BOD interrupt.
+    ///
+    /// The brown-out detector continues to request the interrupt for as
+    /// long as the supply voltage remains below the interrupt level, so an
+    /// interrupt service routine that returns while the supply is still low
+    /// will be called again immediately. To handle the brown-out only once,
+    /// the interrupt service routine should call `disable_interrupt` before
+    /// calling this function.
+    #[inline(always)]
+    pub fn acknowledge_interrupt(&self) {
+        let nvic = lpc81x_pac::NVIC::ptr();
+        unsafe {
+            (*nvic).icpr[0].write(Self::NVIC_BITMASK);
+        }
+    }
+
+    /// Allows the BOD interrupt to wake the device from deep-sleep or
+    /// power-down mode.
+    ///
+    /// The brown-out detector must also be kept powered in those modes
+    /// using `enable_in_deep_sleep`, and the interrupt must be enabled
+    /// using `enable_interrupt`.
+    pub fn enable_wakeup(&mut self) {
+        wakeup::set_peripheral(wakeup::BOD, true);
+    }
+
+    /// Prevents the BOD interrupt from waking the device from deep-sleep or
+    /// power-down mode.
+    pub fn disable_wakeup(&mut self) {
+        wakeup::set_peripheral(wakeup::BOD, false);
+    }
+
+    /// Keeps the brown-out detector powered while the device is in
+    /// deep-sleep or power-down mode, so that it can still reset or wake
+    /// the device.
+    pub fn enable_in_deep_sleep(&mut self) {
+        let syscon = lpc81x_pac::SYSCON::ptr();
+        unsafe { (*syscon).pdsleepcfg.modify(|_, w| w.bod_pd().powered()) }
+    }
+
+    /// Powers down the brown-out detector while the device is in deep-sleep
+    /// or power-down mode, which is the reset behavior.
+    pub fn disable_in_deep_sleep(&mut self) {
+        let syscon = lpc81x_pac::SYSCON::ptr();
+        unsafe {
+            (*syscon)
+                .pdsleepcfg
+                .modify(|_, w| w.bod_pd().powered_down())
+        }
+    }
+
+    /// Returns true if the brown-out detector has reset the device since
+    /// the reset status was last cleared.
+    ///
+    /// This is the same as the `brown_out` flag of the result of
+    /// `Peripherals::reset_cause`.
+    pub fn caused_reset(&self) -> bool {
+        let syscon = lpc81x_pac::SYSCON::ptr();
+        unsafe { (*syscon).sysrststat.read().bod().bit_is_set() }
+    }
+}
+
+/// Selects the supply voltage at which the BOD reset is asserted.
+///
+/// The voltages given here are the typical values from the LPC81x
+/// datasheet. Each level also has a slightly higher de-assertion voltage.
+#[derive(Clone, Copy)]
+pub enum ResetLevel {
+    /// Approximately 2.05V.
+    Level1,
+    /// Approximately 2.35V.
+    Level2,
+    /// Approximately 2.63V.
+    Level3,
+}
+
+/// Selects the supply voltage at which the BOD interrupt is requested.
+///
+/// The voltages given here are the typical values from the LPC81x
+/// datasheet. Each level also has a slightly higher de-assertion voltage.
+#[derive(Clone, Copy)]
+pub enum InterruptLevel {
+    /// Approximately 2.22V.
+    Level1,
+    /// Approximately 2.52V.
+    Level2,
+    /// Approximately 2.80V.
+    Level3,
+}
@@ -0,0 +1,182 @@
+//! Clock configuration.
+//!
+//! Several peripherals need to know the frequencies of the main and system
+//! clocks in order to derive their own timing. The clock configuration is
+//! therefore "frozen" by consuming the `ClockConfig` object from
+//! `Peripherals`, producing a `Clocks` value that describes the final
+//! frequencies and that can then be passed to any methods that need it.
+//! Because `ClockConfig` can only be consumed once, the frequencies recorded
+//! in `Clocks` cannot later become stale.
+//!
+//! Before freezing, `ClockConfig` can select an external clock source from
+//! `source`, multiply its frequency using the system PLL, and divide the
+//! main clock to produce the system clock.
+
+pub mod clkout;
+pub mod source;
+
+/// The frequency of the internal RC oscillator (IRC), which is the source
+/// of the main clock at reset.
+pub const IRC_HZ: u32 = 12_000_000;
+
+// The highest system clock frequency the LPC81x supports.
+pub(crate) const MAX_SYSTEM_HZ: u32 = 30_000_000;
+
+/// The not-yet-frozen clock configuration.
+///
+/// The `SRC` type parameter is the clock source that will drive the main
+/// clock, either directly or via the system PLL. It is initially the
+/// internal RC oscillator, and can be changed using `use_source`.
+pub struct ClockConfig<SRC: source::Source = source::Irc> {
+    source: SRC,
+    pll_multiplier: Option<u8>,
+    system_divider: u8,
+}
+
+impl<SRC: source::Source> !Sync for ClockConfig<SRC> {}
+
+impl ClockConfig<source::Irc> {
+    pub(crate) fn new() -> Self {
+        Self {
+            source: source::Irc::new(),
+            pll_multiplier: None,
+            system_divider: 1,
+        }
+    }
+
+    /// Selects a different clock source to drive the main clock and the
+    /// system PLL once the configuration is frozen.
+    ///
+    /// The clock source object is consumed, so the pins it uses cannot be
+    /// recovered once the configuration is frozen.
+    pub fn use_source<S: source::Source>(self, source: S) -> ClockConfig<S> {
+        ClockConfig {
+            source,
+            pll_multiplier: self.pll_multiplier,
+            system_divider: self.system_divider,
+        }
+    }
+}
+
+impl<SRC: source::Source> ClockConfig<SRC> {
+    /// Drives the main clock from the system PLL, which multiplies the
+    /// frequency of the clock source by the given factor between 1 and 32.
+    ///
+    /// The source frequency must be between 10MHz and 25MHz, and the
+    /// resulting PLL output frequency must not exceed 100MHz. These
+    /// requirements are checked when the configuration is frozen.
+    pub fn use_pll(mut self, multiplier: u8) -> Self {
+        self.pll_multiplier = Some(multiplier);
+        self
+    }
+
+    /// Sets the divider between the main clock and the system clock, which
+    /// must be between 1 and 255.
+    ///
+    /// The system clock, which drives the processor core, must not exceed
+    /// 30MHz. This is checked when the configuration is frozen.
+    pub fn system_clock_divider(mut self, divider: u8) -> Self {
+        self.system_divider = divider;
+        self
+    }
+
+    /// Consumes the clock configuration, applies it, and returns a
+    /// description of the resulting clock frequencies.
+    ///
+    /// Panics if the requested configuration is outside of the operating
+    /// limits of the system PLL or the processor core.
+    pub fn freeze(self) -> Clocks {
+        let source_hz = self.source.frequency();
+        let main = match self.pll_multiplier {
+            Some(m) => {
+                assert!((1..=32).contains(&m));
+                assert!((10_000_000..=25_000_000).contains(&source_hz));
+                source_hz * (m as u32)
+            }
+            None => source_hz,
+        };
+        assert!(main <= 100_000_000);
+        assert!(self.system_divider >= 1);
+        let system = main / (self.system_divider as u32);
+        assert!(system <= MAX_SYSTEM_HZ);
+
+        let syscon = lpc81x_pac::SYSCON::ptr();
+        unsafe {
+            // The PLL input selection feeds both the PLL itself and the
+            // main clock's "PLL input" option. Changes take effect only after
+            // the update enable register transitions from zero to one.
+            (*syscon)
+                .syspllclksel
+                .write(|w| w.sel().bits(SRC::PLL_INPUT));
+            (*syscon).syspllclkuen.write(|w| w.ena().no_change());
+            (*syscon)
+                .syspllclkuen
+                .write(|w| w.ena().update_clock_source());
+
+            if let Some(m) = self.pll_multiplier {
+                // The current-controlled oscillator inside the PLL must run
+                // between 156MHz and 320MHz, at 2 * P times the output
+                // frequency.
+                let mut psel: u8 = 0;
+                while psel < 3 && (main * (2 << psel)) < 156_000_000 {
+                    psel += 1;
+                }
+                (*syscon).pdruncfg.modify(|_, w| w.syspll_pd().powered());
+                (*syscon)
+                    .syspllctrl
+                    .write(|w| w.msel().bits(m - 1).psel().bits(psel));
+                while (*syscon).syspllstat.read().lock().bit_is_clear() {}
+            }
+
+            // The divider must be in place before switching the main clock so
+            // that the system clock never exceeds its limit.
+            (*syscon)
+                .sysahbclkdiv
+                .write(|w| w.div().bits(self.system_divider));
+
+            (*syscon).mainclksel.write(|w| {
+                if self.pll_multiplier.is_some() {
+                    w.sel().pll_output()
+                } else if SRC::IS_IRC {
+                    w.sel().irc_oscillator()
+                } else {
+                    w.sel().pll_input()
+                }
+            });
+            (*syscon).mainclkuen.write(|w| w.ena().no_change());
+            (*syscon)
+                .mainclkuen
+                .write(|w| w.ena().update_clock_source());
+        }
+
+        Clocks { main, system }
+    }
+}
+
+/// Describes the frozen clock frequencies.
+///
+/// A `Clocks` value can be obtained only by calling `ClockConfig::freeze`,
+/// after which the clock configuration can no longer be changed.
+#[derive(Clone, Copy, Debug)]
+pub struct Clocks {
+    main: u32,
+    system: u32,
+}
+
+impl Clocks {
+    /// Returns the frequency of the main clock in Hz.
+    ///
+    /// The main clock drives several peripheral clocks, including the
+    /// USART clock and the IOCON glitch filter clocks.
+    pub fn main_clock(&self) -> u32 {
+        self.main
+    }
+
+    /// Returns the frequency of the system clock in Hz.
+    ///
+    /// The system clock drives the processor core, the memories, and the
+    /// peripheral buses.
+    pub fn system_clock(&self) -> u32 {
+        self.system
+    }
+}
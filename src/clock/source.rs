@@ -0,0 +1,276 @@
+//! Clock sources that can drive the main clock and the system PLL.
+//!
+//! At reset the main clock runs from the internal RC oscillator (IRC). For
+//! more accurate timing, such as for precise UART baud rates, an external
+//! crystal can be connected to the XTALIN and XTALOUT fixed-pin functions on
+//! GPIO pins 8 and 9 to drive the system oscillator, or an external clock
+//! signal can be applied to the CLKIN fixed-pin function on GPIO pin 1.
+//!
+//! Creating a source object consumes the relevant pins and starts the
+//! source running. The source can then be given to
+//! `ClockConfig::use_source` so that the main clock and system PLL will use
+//! it once the clock configuration is frozen.
+//!
+//! ```rust
+//! let osc = hal::clock::source::SystemOscillator::crystal(
+//!     pins.gpio8,
+//!     pins.gpio9,
+//!     12_000_000,
+//! );
+//! let clocks = p.clock.use_source(osc).freeze();
+//! ```
+
+use crate::pins;
+use core::marker::PhantomData;
+
+/// The internal RC oscillator, which is the clock source at reset.
+pub struct Irc(PhantomData<()>);
+
+impl !Sync for Irc {}
+
+impl Irc {
+    pub(crate) fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+unsafe impl Source for Irc {
+    const PLL_INPUT: u8 = 0b00;
+    const IS_IRC: bool = true;
+
+    fn frequency(&self) -> u32 {
+        super::IRC_HZ
+    }
+}
+
+/// The system oscillator, driven either by a crystal connected between
+/// XTALIN and XTALOUT or by an external clock signal on XTALIN.
+///
+/// The `XTALOUT` type parameter records whether GPIO pin 9 is in use as
+/// XTALOUT, which is the case only when a crystal is connected.
+pub struct SystemOscillator<XTALOUT: pins::PinAssignment> {
+    hz: u32,
+    _xtalout: PhantomData<XTALOUT>,
+}
+
+impl<XTALOUT: pins::PinAssignment> !Sync for SystemOscillator<XTALOUT> {}
+
+impl SystemOscillator<pins::mode::Unassigned> {
+    /// Consumes GPIO pins 8 and 9 and starts the system oscillator with a
+    /// crystal of the given frequency connected between them.
+    ///
+    /// The crystal frequency must be between 1MHz and 25MHz. This function
+    /// waits for the oscillator to stabilize before returning.
+    pub fn crystal(
+        xtalin: pins::pin::Pin8<pins::mode::Unassigned>,
+        xtalout: pins::pin::Pin9<pins::mode::Unassigned>,
+        hz: u32,
+    ) -> SystemOscillator<XtalOutPin> {
+        set_pulls(true, true, false);
+        let swm = lpc81x_pac::SWM::ptr();
+        unsafe {
+            (*swm).pinenable0.modify(|_, w| {
+                w.xtalin_en()
+                    .enable_xtalin_this_()
+                    .xtalout_en()
+                    .enable_xtalout_this()
+            })
+        }
+        Self::start(hz, false);
+        unused(xtalin);
+        unused(xtalout);
+        SystemOscillator::new(hz)
+    }
+
+    /// Consumes GPIO pin 8 and starts the system oscillator in bypass mode,
+    /// with an external clock signal of the given frequency applied to it.
+    ///
+    /// The external clock frequency must be between 1MHz and 25MHz.
+    pub fn bypass(
+        xtalin: pins::pin::Pin8<pins::mode::Unassigned>,
+        hz: u32,
+    ) -> SystemOscillator<pins::mode::Unassigned> {
+        set_pulls(true, false, false);
+        let swm = lpc81x_pac::SWM::ptr();
+        unsafe {
+            (*swm)
+                .pinenable0
+                .modify(|_, w| w.xtalin_en().enable_xtalin_this_())
+        }
+        Self::start(hz, true);
+        unused(xtalin);
+        SystemOscillator::new(hz)
+    }
+
+    /// Stops the system oscillator and returns GPIO pin 8.
+    pub fn disable(self) -> pins::pin::Pin8<pins::mode::Unassigned> {
+        Self::stop(false);
+        pin_type_as_is()
+    }
+}
+
+impl SystemOscillator<XtalOutPin> {
+    /// Stops the system oscillator and returns GPIO pins 8 and 9.
+    pub fn disable(
+        self,
+    ) -> (
+        pins::pin::Pin8<pins::mode::Unassigned>,
+        pins::pin::Pin9<pins::mode::Unassigned>,
+    ) {
+        Self::stop(true);
+        (pin_type_as_is(), pin_type_as_is())
+    }
+}
+
+impl<XTALOUT: pins::PinAssignment> SystemOscillator<XTALOUT> {
+    fn new(hz: u32) -> Self {
+        Self {
+            hz,
+            _xtalout: PhantomData,
+        }
+    }
+
+    fn start(hz: u32, bypass: bool) {
+        let syscon = lpc81x_pac::SYSCON::ptr();
+        unsafe {
+            (*syscon).sysoscctrl.write(|w| {
+                w.bypass().bit(bypass);
+                if hz > 20_000_000 {
+                    w.freqrange()._15_25_mhz_frequenc()
+                } else {
+                    w.freqrange()._1_20_mhz_frequency()
+                }
+            });
+            (*syscon).pdruncfg.modify(|_, w| w.sysosc_pd().powered());
+        }
+
+        // The user manual calls for a delay of around 500µs after powering
+        // the oscillator before it can be used. A source can be created
+        // after some other clock configuration has been frozen, so we count
+        // cycles at the fastest possible system clock. The delay is longer
+        // than necessary at slower clocks, but never shorter.
+        cortex_m::asm::delay(super::MAX_SYSTEM_HZ / 2_000);
+    }
+
+    fn stop(xtalout: bool) {
+        let syscon = lpc81x_pac::SYSCON::ptr();
+        let swm = lpc81x_pac::SWM::ptr();
+        unsafe {
+            (*syscon)
+                .pdruncfg
+                .modify(|_, w| w.sysosc_pd().powered_down());
+            (*swm).pinenable0.modify(|_, w| {
+                w.xtalin_en()
+                    .disable_xtalin_gpio()
+                    .xtalout_en()
+                    .disable_xtalout_gpi()
+            })
+        }
+        set_pulls(true, xtalout, true);
+    }
+}
+
+// The user manual calls for the pull-up/pull-down resistors to be removed
+// from the oscillator pins before connecting them to the oscillator, so
+// that they don't load the crystal. Passing `pull_up` restores the pull-up
+// resistors that are enabled at reset, for when the pins are returned.
+fn set_pulls(xtalin: bool, xtalout: bool, pull_up: bool) {
+    let iocon = lpc81x_pac::IOCON::ptr();
+    let mode = if pull_up {
+        pins::iocon::MODE_PULL_UP
+    } else {
+        pins::iocon::MODE_INACTIVE
+    };
+    pins::iocon::enable_clock();
+    unsafe {
+        if xtalin {
+            (*iocon)
+                .pio0_8
+                .modify(|r, w| w.bits((r.bits() & !pins::iocon::MODE_MASK) | mode));
+        }
+        if xtalout {
+            (*iocon)
+                .pio0_9
+                .modify(|r, w| w.bits((r.bits() & !pins::iocon::MODE_MASK) | mode));
+        }
+    }
+}
+
+unsafe impl<XTALOUT: pins::PinAssignment> Source for SystemOscillator<XTALOUT> {
+    const PLL_INPUT: u8 = 0b01;
+    const IS_IRC: bool = false;
+
+    fn frequency(&self) -> u32 {
+        self.hz
+    }
+}
+
+/// An external clock signal applied to the CLKIN fixed-pin function on
+/// GPIO pin 1.
+pub struct ClkIn {
+    hz: u32,
+}
+
+impl !Sync for ClkIn {}
+
+impl ClkIn {
+    /// Consumes GPIO pin 1 and connects it to CLKIN, where an external clock
+    /// signal of the given frequency is expected.
+    pub fn new(pin: pins::pin::Pin1<pins::mode::Unassigned>, hz: u32) -> Self {
+        let swm = lpc81x_pac::SWM::ptr();
+        unsafe {
+            (*swm)
+                .pinenable0
+                .modify(|_, w| w.clkin().enable_clkin_this_f())
+        }
+        unused(pin);
+        Self { hz }
+    }
+
+    /// Disconnects CLKIN and returns GPIO pin 1.
+    pub fn disable(self) -> pins::pin::Pin1<pins::mode::Unassigned> {
+        let swm = lpc81x_pac::SWM::ptr();
+        unsafe {
+            (*swm)
+                .pinenable0
+                .modify(|_, w| w.clkin().disable_clkin_gpio_())
+        }
+        pin_type_as_is()
+    }
+}
+
+unsafe impl Source for ClkIn {
+    const PLL_INPUT: u8 = 0b11;
+    const IS_IRC: bool = false;
+
+    fn frequency(&self) -> u32 {
+        self.hz
+    }
+}
+
+// Trait implemented by types representing clock sources that can drive the
+// system PLL and main clock.
+//
+// Only types in the `lpx81x-hal` crate may implement this trait.
+pub unsafe trait Source {
+    // PLL_INPUT is the value of SYSPLLCLKSEL that selects this source.
+    const PLL_INPUT: u8;
+
+    // IS_IRC is true only for the IRC, which the main clock can select
+    // directly without going through the PLL input selector.
+    const IS_IRC: bool;
+
+    fn frequency(&self) -> u32;
+}
+
+type XtalOutPin = pins::mode::Assigned<pins::pin::Pin9<pins::mode::Unassigned>>;
+
+#[inline(always)]
+fn unused<T>(_v: T) {}
+
+fn pin_type_as_is<T: pins::Pin>() -> T {
+    // This is safe because our pin types are zero-length anyway, and so
+    // "filling them with zeroes" is indistinguishable from properly
+    // initializing them.
+    unsafe { core::mem::zeroed() }
+}
@@ -0,0 +1,542 @@
+//! A software analog-to-digital converter built from the comparator and
+//! its voltage ladder.
+//!
+//! The LPC81x has no ADC peripheral, but slow-moving analog signals can be
+//! measured by comparing them against each of the 32 voltage ladder steps in
+//! turn. `Adc` does this using successive approximation, so each conversion
+//! takes five comparisons.
+//!
+//! Results are ladder steps between 0 and 31, where step `n` represents the
+//! voltage `VDD * n / 31`. Because the ladder is referenced to VDD, the
+//! results are ratiometric. Use `Adc::vdd_millivolts` to measure VDD itself
+//! against the internal bandgap reference, and then `Adc::step_millivolts`
+//! to convert results to absolute voltages.
+//!
+//! ```rust
+//! let cmp = p.cmp.activate(cmp_cfg).with_input1(pins.gpio0);
+//! let mut adc = hal::cmp::adc::Adc::new(cmp, 180);
+//! let vdd = adc.vdd_millivolts();
+//! let step: u8 = adc.read(&mut hal::cmp::adc::Input1).unwrap();
+//! let mv = hal::cmp::adc::Adc::step_millivolts(step, vdd);
+//! ```
+//!
+//! # RC timing
+//!
+//! For finer resolution, `Adc::into_rc_timing` returns an `RcAdc` that
+//! also times how long an external capacitor takes to charge past the
+//! input voltage, using a capture in the state configurable timer (SCT).
+//! This needs a capacitor from GPIO pin 0 (ACMP_I1) to ground, charged
+//! through a resistor from a digital output pin, with the voltage to
+//! measure on GPIO pin 1 (ACMP_I2). The comparator output must also be
+//! assigned to a pin, because that is how it reaches the SCT.
+//!
+//! Each conversion first finds the ladder step below the input as usual,
+//! and then times the capacitor charging past the input and past the
+//! ladder steps either side of it. The charging curve is close to linear
+//! over a single ladder step, so interpolating between those times gives
+//! the input voltage in 256ths of a ladder step, and the result does not
+//! depend on the exact resistor and capacitor values.
+//!
+//! ```rust
+//! let cmp = p
+//!     .cmp
+//!     .activate(cmp_cfg)
+//!     .with_input1(pins.gpio0)
+//!     .with_input2(pins.gpio1)
+//!     .with_output(pins.gpio7);
+//! let charge = pins.gpio6.to_digital_output(false);
+//! let timing = hal::cmp::adc::RcTiming {
+//!     discharge_cycles: 12_000,
+//!     timeout_cycles: 120_000,
+//! };
+//! let mut adc = hal::cmp::adc::Adc::new(cmp, 180).into_rc_timing(
+//!     p.sct,
+//!     charge,
+//!     hal::cmp::SctInput::Input0,
+//!     timing,
+//! );
+//! let vdd = adc.vdd_millivolts().unwrap();
+//! let value: u16 = adc.read(&mut hal::cmp::adc::Input2).unwrap();
+//! let mv = hal::cmp::adc::RcAdc::fine_millivolts(value, vdd);
+//! ```
+
+use super::{cfg, mode, SctInput, CMP};
+use crate::{pins, sct};
+use embedded_hal::digital::v2::OutputPin;
+
+/// The nominal voltage of the comparator's internal bandgap reference.
+///
+/// The LPC81x datasheet specifies the bandgap as 900mV typical, but the
+/// actual value varies between individual parts.
+pub const BANDGAP_MILLIVOLTS: u32 = 900;
+
+/// The highest step number of the voltage ladder.
+pub const MAX_STEP: u8 = 31;
+
+/// The number of parts each ladder step is divided into by the results of
+/// `RcAdc` conversions.
+pub const FRACTIONS_PER_STEP: u16 = 256;
+
+/// An `embedded-hal` one-shot ADC implemented using the analog comparator.
+///
+/// `Adc` takes ownership of an active comparator and reconfigures it for each
+/// conversion, so the comparator cannot be used for anything else until it
+/// is released with `release`.
+pub struct Adc<I1, I2, OUT>
+where
+    I1: pins::PinAssignment,
+    I2: pins::PinAssignment,
+    OUT: pins::PinAssignment,
+{
+    cmp: CMP<mode::Active, I1, I2, OUT>,
+    settle_cycles: u32,
+}
+
+impl<I1, I2, OUT> Adc<I1, I2, OUT>
+where
+    I1: pins::PinAssignment,
+    I2: pins::PinAssignment,
+    OUT: pins::PinAssignment,
+{
+    /// Consumes the given active comparator and returns an ADC that uses it.
+    ///
+    /// `settle_cycles` is the number of system clock cycles to wait after
+    /// each change to the voltage ladder before sampling the comparator
+    /// output. The LPC81x datasheet gives the ladder settling time as 15µs,
+    /// which is 180 cycles at the 12MHz reset clock frequency.
+    pub fn new(cmp: CMP<mode::Active, I1, I2, OUT>, settle_cycles: u32) -> Self {
+        Self { cmp, settle_cycles }
+    }
+
+    /// Consumes the ADC and returns the comparator it was using.
+    ///
+    /// The voltage ladder is disabled and the comparator is left with both
+    /// inputs connected to the ladder, so it should be reconfigured before
+    /// any further use.
+    pub fn release(mut self) -> CMP<mode::Active, I1, I2, OUT> {
+        self.cmp.disable_ladder();
+        self.cmp.reconfigure(cfg::RESET_CONFIG);
+        self.cmp
+    }
+
+    /// Measures the supply voltage VDD, in millivolts.
+    ///
+    /// This compares the internal bandgap reference against the voltage
+    /// ladder, so the resolution is limited by the ladder step size: near
+    /// 3.3V, adjacent possible results are around 400mV apart. The result
+    /// is the midpoint of the range that the measurement could represent,
+    /// and its accuracy also depends on the variation in the bandgap voltage
+    /// between parts.
+    pub fn vdd_millivolts(&mut self) -> u32 {
+        let step = self.convert(cfg::Input::Bandgap) as u32;
+
+        // The bandgap voltage is between ladder steps `step` and `step + 1`,
+        // so we assume it lies halfway between them:
+        //     BANDGAP = VDD * (step + 0.5) / 31
+        // We double both sides of the fraction to stay in integer math.
+        BANDGAP_MILLIVOLTS * (MAX_STEP as u32) * 2 / (step * 2 + 1)
+    }
+
+    /// Converts a ladder step returned from a conversion into millivolts,
+    /// given the supply voltage in millivolts.
+    pub fn step_millivolts(step: u8, vdd_millivolts: u32) -> u32 {
+        vdd_millivolts * (step as u32) / (MAX_STEP as u32)
+    }
+
+    // Performs a successive approximation conversion of the given input,
+    // returning the highest ladder step whose voltage is below the voltage
+    // at the input.
+    fn convert(&mut self, input: cfg::Input) -> u8 {
+        self.cmp.reconfigure(cfg::Config {
+            positive: input,
+            negative: cfg::Input::VoltageLadder,
+            hysteresis: cfg::Hysteresis::None,
+            edge: cfg::Edge::Both,
+            output: cfg::OutputMode::Direct,
+        });
+
+        let mut result: u8 = 0;
+        for bit in (0..5).rev() {
+            let trial = result | (1 << bit);
+            self.cmp.set_ladder(trial);
+            cortex_m::asm::delay(self.settle_cycles);
+            if self.cmp.output() {
+                result = trial;
+            }
+        }
+        self.cmp.disable_ladder();
+        result
+    }
+}
+
+/// Selects the comparator's ACMP_I1 input (GPIO pin 0) as an ADC channel.
+pub struct Input1;
+
+/// Selects the comparator's ACMP_I2 input (GPIO pin 1) as an ADC channel.
+pub struct Input2;
+
+/// Selects the internal bandgap reference as an ADC channel.
+///
+/// This is mainly useful for measuring VDD; see `Adc::vdd_millivolts`.
+pub struct Bandgap;
+
+impl<I1, I2, OUT> embedded_hal::adc::Channel<Adc<I1, I2, OUT>> for Input1
+where
+    I1: pins::PinAssignment,
+    I2: pins::PinAssignment,
+    OUT: pins::PinAssignment,
+{
+    // Channel IDs are the comparator's input selection values.
+    type ID = u8;
+
+    fn channel() -> u8 {
+        1
+    }
+}
+
+impl<I1, I2, OUT> embedded_hal::adc::Channel<Adc<I1, I2, OUT>> for Input2
+where
+    I1: pins::PinAssignment,
+    I2: pins::PinAssignment,
+    OUT: pins::PinAssignment,
+{
+    type ID = u8;
+
+    fn channel() -> u8 {
+        2
+    }
+}
+
+impl<I1, I2, OUT> embedded_hal::adc::Channel<Adc<I1, I2, OUT>> for Bandgap
+where
+    I1: pins::PinAssignment,
+    I2: pins::PinAssignment,
+    OUT: pins::PinAssignment,
+{
+    type ID = u8;
+
+    fn channel() -> u8 {
+        6
+    }
+}
+
+/// Reading from `Input1` is possible only when GPIO pin 0 is connected to
+/// the comparator.
+impl<I2, OUT> embedded_hal::adc::OneShot<Adc<Input1Pin, I2, OUT>, u8, Input1>
+    for Adc<Input1Pin, I2, OUT>
+where
+    I2: pins::PinAssignment,
+    OUT: pins::PinAssignment,
+{
+    type Error = !;
+
+    /// Performs a blocking conversion, returning a ladder step between 0
+    /// and 31.
+    fn read(&mut self, _pin: &mut Input1) -> nb::Result<u8, !> {
+        Ok(self.convert(cfg::Input::Input1))
+    }
+}
+
+/// Reading from `Input2` is possible only when GPIO pin 1 is connected to
+/// the comparator.
+impl<I1, OUT> embedded_hal::adc::OneShot<Adc<I1, Input2Pin, OUT>, u8, Input2>
+    for Adc<I1, Input2Pin, OUT>
+where
+    I1: pins::PinAssignment,
+    OUT: pins::PinAssignment,
+{
+    type Error = !;
+
+    /// Performs a blocking conversion, returning a ladder step between 0
+    /// and 31.
+    fn read(&mut self, _pin: &mut Input2) -> nb::Result<u8, !> {
+        Ok(self.convert(cfg::Input::Input2))
+    }
+}
+
+impl<I1, I2, OUT> embedded_hal::adc::OneShot<Adc<I1, I2, OUT>, u8, Bandgap> for Adc<I1, I2, OUT>
+where
+    I1: pins::PinAssignment,
+    I2: pins::PinAssignment,
+    OUT: pins::PinAssignment,
+{
+    type Error = !;
+
+    /// Performs a blocking conversion, returning a ladder step between 0
+    /// and 31.
+    fn read(&mut self, _pin: &mut Bandgap) -> nb::Result<u8, !> {
+        Ok(self.convert(cfg::Input::Bandgap))
+    }
+}
+
+impl<OUT: pins::Pin> Adc<Input1Pin, Input2Pin, pins::mode::Assigned<OUT>> {
+    /// Consumes the ADC and returns an `RcAdc` that uses RC timing for
+    /// finer resolution, taking ownership of the SCT and of the digital
+    /// output pin that charges the capacitor.
+    ///
+    /// The comparator output is connected to the given SCT input, which
+    /// must not be assigned to any other pin. See the module documentation
+    /// for the required circuit.
+    pub fn into_rc_timing<CHARGE: OutputPin<Error = !>>(
+        mut self,
+        mut sct: sct::SCT,
+        charge: CHARGE,
+        input: SctInput,
+        timing: RcTiming,
+    ) -> RcAdc<OUT, CHARGE> {
+        let iosel = match input {
+            SctInput::Input0 => 0,
+            SctInput::Input1 => 1,
+            SctInput::Input2 => 2,
+            SctInput::Input3 => 3,
+        };
+        self.cmp.connect_to_sct(input);
+        sct.power_up();
+        let periph = lpc81x_pac::SCT::ptr();
+        unsafe {
+            // A single 32-bit counter, clocked by the system clock, with
+            // the inputs synchronized to it.
+            (*periph).config.write(|w| w.unify().unified());
+            // Register 0 is a capture register, loaded by event 0. Its
+            // capture control register shares an address with match reload
+            // register 0, which is what the PAC models.
+            (*periph).regmode.write(|w| w.bits(1));
+            (*periph).matchrel[0].write(|w| w.bits(1));
+            // Event 0 happens in state 0 when the comparator output rises,
+            // and it also halts the counter.
+            (*periph).ev0_state.write(|w| w.bits(1));
+            (*periph).ev0_ctrl.write(|w| {
+                w.iosel()
+                    .bits(iosel)
+                    .outsel()
+                    .selects_the_inputs_e()
+                    .iocond()
+                    .rise()
+                    .combmode()
+                    .io_uses_the_specifi()
+            });
+            (*periph).halt.write(|w| w.bits(1));
+        }
+        RcAdc {
+            adc: self,
+            sct,
+            charge,
+            input,
+            timing,
+        }
+    }
+}
+
+/// The timing configuration for `RcAdc`, in system clock cycles.
+#[derive(Clone, Copy, Debug)]
+pub struct RcTiming {
+    /// How long to hold the charge pin low before each timed charge. This
+    /// must be long enough to fully discharge the capacitor, which takes
+    /// about five RC time constants.
+    pub discharge_cycles: u32,
+
+    /// How long to wait for the capacitor voltage to pass the voltage
+    /// being timed before giving up. Charging to 30/31 of VDD takes about
+    /// 3.4 RC time constants.
+    pub timeout_cycles: u32,
+}
+
+/// The error returned when the capacitor voltage did not pass the voltage
+/// being timed within `RcTiming::timeout_cycles`.
+#[derive(Clone, Copy, Debug)]
+pub struct Timeout;
+
+/// An `embedded-hal` one-shot ADC that combines ladder successive
+/// approximation with RC timing to give results in 256ths of a ladder step.
+///
+/// Results are between 0 and `MAX_STEP * FRACTIONS_PER_STEP`. The fraction
+/// is interpolated only for inputs below ladder step 30, because the
+/// capacitor never charges past ladder step 31, which is VDD itself. Higher
+/// inputs give whole ladder steps.
+pub struct RcAdc<OUT: pins::Pin, CHARGE: OutputPin<Error = !>> {
+    adc: Adc<Input1Pin, Input2Pin, pins::mode::Assigned<OUT>>,
+    sct: sct::SCT,
+    charge: CHARGE,
+    input: SctInput,
+    timing: RcTiming,
+}
+
+impl<OUT: pins::Pin, CHARGE: OutputPin<Error = !>> RcAdc<OUT, CHARGE> {
+    /// Consumes the RC-timing ADC and returns the ADC it was built on, along
+    /// with the SCT and the charge pin.
+    ///
+    /// The comparator output is disconnected from the SCT input, and the
+    /// SCT is left with its clock disabled.
+    pub fn release(
+        self,
+    ) -> (
+        Adc<Input1Pin, Input2Pin, pins::mode::Assigned<OUT>>,
+        sct::SCT,
+        CHARGE,
+    ) {
+        let RcAdc {
+            mut adc,
+            mut sct,
+            charge,
+            input,
+            ..
+        } = self;
+        adc.cmp.disconnect_from_sct(input);
+        sct.power_down();
+        (adc, sct, charge)
+    }
+
+    /// Measures the supply voltage VDD, in millivolts.
+    ///
+    /// This works like `Adc::vdd_millivolts`, but measures the bandgap
+    /// reference with RC timing, so adjacent possible results are only a
+    /// few millivolts apart. The accuracy still depends on the variation in
+    /// the bandgap voltage between parts.
+    pub fn vdd_millivolts(&mut self) -> Result<u32, Timeout> {
+        let value = core::cmp::max(self.convert(cfg::Input::Bandgap)?, 1) as u32;
+        Ok(BANDGAP_MILLIVOLTS * (MAX_STEP as u32) * (FRACTIONS_PER_STEP as u32) / value)
+    }
+
+    /// Converts a result returned from a conversion into millivolts, given
+    /// the supply voltage in millivolts.
+    pub fn fine_millivolts(value: u16, vdd_millivolts: u32) -> u32 {
+        vdd_millivolts * (value as u32) / ((MAX_STEP as u32) * (FRACTIONS_PER_STEP as u32))
+    }
+
+    // Finds the ladder step below the given input and then interpolates
+    // between the charging times for that step and the next using the
+    // charging time for the input itself.
+    fn convert(&mut self, input: cfg::Input) -> Result<u16, Timeout> {
+        let step = self.adc.convert(input);
+        let whole = (step as u16) * FRACTIONS_PER_STEP;
+        if step >= MAX_STEP - 1 {
+            return Ok(whole);
+        }
+
+        let t_input = self.charge_time(input)?;
+        // Ladder step 0 is ground, which the capacitor passes immediately.
+        let t_low = if step == 0 {
+            0
+        } else {
+            self.charge_time_ladder(step)?
+        };
+        let t_high = self.charge_time_ladder(step + 1)?;
+        self.adc.cmp.disable_ladder();
+        if t_high <= t_low {
+            return Ok(whole);
+        }
+
+        let fraction = (t_input.saturating_sub(t_low) as u64) * (FRACTIONS_PER_STEP as u64)
+            / ((t_high - t_low) as u64);
+        Ok(whole + core::cmp::min(fraction, (FRACTIONS_PER_STEP - 1) as u64) as u16)
+    }
+
+    fn charge_time_ladder(&mut self, step: u8) -> Result<u32, Timeout> {
+        self.adc.cmp.set_ladder(step);
+        cortex_m::asm::delay(self.adc.settle_cycles);
+        self.charge_time(cfg::Input::VoltageLadder)
+    }
+
+    // Discharges the capacitor and then returns the number of system clock
+    // cycles it takes to charge past the given comparator input, as
+    // captured by the SCT.
+    fn charge_time(&mut self, negative: cfg::Input) -> Result<u32, Timeout> {
+        self.adc.cmp.reconfigure(cfg::Config {
+            positive: cfg::Input::Input1,
+            negative,
+            hysteresis: cfg::Hysteresis::None,
+            edge: cfg::Edge::Rising,
+            output: cfg::OutputMode::Direct,
+        });
+
+        // The pin's error type is `!`, so there's nothing to handle here.
+        let _ = self.charge.set_low();
+        cortex_m::asm::delay(self.timing.discharge_cycles);
+
+        let periph = lpc81x_pac::SCT::ptr();
+        unsafe {
+            (*periph)
+                .ctrl
+                .modify(|_, w| w.halt_l().set_bit().clrctr_l().set_bit());
+            (*periph).evflag.write(|w| w.bits(1));
+            (*periph).ctrl.modify(|_, w| w.halt_l().clear_bit());
+        }
+        let _ = self.charge.set_high();
+
+        let result = loop {
+            let (flags, count) = unsafe {
+                (
+                    (*periph).evflag.read().bits(),
+                    (*periph).count.read().bits(),
+                )
+            };
+            if flags & 1 != 0 {
+                // Register 0 is in capture mode, where its address reads
+                // the capture register rather than the match register.
+                break Ok(unsafe { (*periph).match_[0].read().bits() });
+            }
+            if count >= self.timing.timeout_cycles {
+                break Err(Timeout);
+            }
+        };
+        unsafe { (*periph).ctrl.modify(|_, w| w.halt_l().set_bit()) }
+        let _ = self.charge.set_low();
+        result
+    }
+}
+
+impl<OUT, CHARGE> embedded_hal::adc::Channel<RcAdc<OUT, CHARGE>> for Input2
+where
+    OUT: pins::Pin,
+    CHARGE: OutputPin<Error = !>,
+{
+    type ID = u8;
+
+    fn channel() -> u8 {
+        2
+    }
+}
+
+impl<OUT, CHARGE> embedded_hal::adc::Channel<RcAdc<OUT, CHARGE>> for Bandgap
+where
+    OUT: pins::Pin,
+    CHARGE: OutputPin<Error = !>,
+{
+    type ID = u8;
+
+    fn channel() -> u8 {
+        6
+    }
+}
+
+impl<OUT, CHARGE> embedded_hal::adc::OneShot<RcAdc<OUT, CHARGE>, u16, Input2> for RcAdc<OUT, CHARGE>
+where
+    OUT: pins::Pin,
+    CHARGE: OutputPin<Error = !>,
+{
+    type Error = Timeout;
+
+    /// Performs a blocking conversion, returning a result in 256ths of a
+    /// ladder step.
+    fn read(&mut self, _pin: &mut Input2) -> nb::Result<u16, Timeout> {
+        Ok(self.convert(cfg::Input::Input2)?)
+    }
+}
+
+impl<OUT, CHARGE> embedded_hal::adc::OneShot<RcAdc<OUT, CHARGE>, u16, Bandgap>
+    for RcAdc<OUT, CHARGE>
+where
+    OUT: pins::Pin,
+    CHARGE: OutputPin<Error = !>,
+{
+    type Error = Timeout;
+
+    /// Performs a blocking conversion, returning a result in 256ths of a
+    /// ladder step.
+    fn read(&mut self, _pin: &mut Bandgap) -> nb::Result<u16, Timeout> {
+        Ok(self.convert(cfg::Input::Bandgap)?)
+    }
+}
+
+type Input1Pin = pins::mode::Assigned<pins::pin::Pin0<pins::mode::Unassigned>>;
+type Input2Pin = pins::mode::Assigned<pins::pin::Pin1<pins::mode::Unassigned>>;
@@ -0,0 +1,56 @@
+pub(crate) const RESET_CONFIG: Config = Config {
+    positive: Input::VoltageLadder,
+    negative: Input::VoltageLadder,
+    hysteresis: Hysteresis::None,
+    edge: Edge::Falling,
+    output: OutputMode::Direct,
+};
+
+pub struct Config {
+    pub positive: Input,
+    pub negative: Input,
+    pub hysteresis: Hysteresis,
+    pub edge: Edge,
+    pub output: OutputMode,
+}
+
+/// Selects one of the signals that can be connected to either of the
+/// comparator inputs.
+#[derive(Clone, Copy)]
+pub enum Input {
+    /// The output of the internal voltage ladder.
+    VoltageLadder,
+
+    /// The fixed-pin function ACMP_I1, on GPIO pin 0.
+    Input1,
+
+    /// The fixed-pin function ACMP_I2, on GPIO pin 1.
+    Input2,
+
+    /// The internal bandgap reference voltage, nominally 900mV.
+    Bandgap,
+}
+
+pub enum Hysteresis {
+    None,
+    MilliVolts5,
+    MilliVolts10,
+    MilliVolts20,
+}
+
+/// Selects which transitions of the comparator output are considered to
+/// be edges for the purpose of edge detection and the comparator interrupt.
+pub enum Edge {
+    Falling,
+    Rising,
+    Both,
+}
+
+pub enum OutputMode {
+    /// The comparator output is used directly.
+    Direct,
+
+    /// The comparator output is synchronized to the bus clock before it is
+    /// passed to other peripherals.
+    Synchronized,
+}
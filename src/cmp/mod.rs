@@ -0,0 +1,460 @@
+//! Interface to the analog comparator.
+
+use crate::pins;
+use core::marker::PhantomData;
+
+pub mod adc;
+pub mod cfg;
+pub mod mode;
+
+/// Represents the analog comparator peripheral.
+///
+/// The comparator starts in an inactive (powered down) state. Call `activate`
+/// to power it up and select which signals are connected to its positive and
+/// negative inputs.
+///
+/// The external inputs ACMP_I1 and ACMP_I2 are fixed-pin functions on GPIO
+/// pins 0 and 1 respectively, so `with_input1` and `with_input2` take those
+/// specific pins. The comparator output ACMP_O is a movable function that
+/// can be assigned to any unassigned pin using `with_output`.
+pub struct CMP<MODE, I1, I2, OUT>
+where
+    MODE: Mode,
+    I1: pins::PinAssignment,
+    I2: pins::PinAssignment,
+    OUT: pins::PinAssignment,
+{
+    mode: PhantomData<MODE>,
+    i1: PhantomData<I1>,
+    i2: PhantomData<I2>,
+    out: PhantomData<OUT>,
+}
+
+/// The comparator object represents access to a single system peripheral,
+/// so it's not safe to share it across multiple threads without some external
+/// concurrency control mechanisms.
+impl<MODE, I1, I2, OUT> !Sync for CMP<MODE, I1, I2, OUT> {}
+
+impl<MODE, I1, I2, OUT> CMP<MODE, I1, I2, OUT>
+where
+    MODE: Mode,
+    I1: pins::PinAssignment,
+    I2: pins::PinAssignment,
+    OUT: pins::PinAssignment,
+{
+    #[inline(always)]
+    pub(crate) fn new() -> Self {
+        Self {
+            mode: PhantomData,
+            i1: PhantomData,
+            i2: PhantomData,
+            out: PhantomData,
+        }
+    }
+
+    #[inline(always)]
+    fn select_output(pin: u8) {
+        let swm = lpc81x_pac::SWM::ptr();
+        unsafe { (*swm).pinassign8.modify(|_, w| w.acmp_o_o().bits(pin)) }
+    }
+
+    #[inline(always)]
+    fn select_sct_input(input: SctInput, pin: u8) {
+        let swm = lpc81x_pac::SWM::ptr();
+        unsafe {
+            match input {
+                SctInput::Input0 => (*swm).pinassign5.modify(|_, w| w.ctin_0_i().bits(pin)),
+                SctInput::Input1 => (*swm).pinassign6.modify(|_, w| w.ctin_1_i().bits(pin)),
+                SctInput::Input2 => (*swm).pinassign6.modify(|_, w| w.ctin_2_i().bits(pin)),
+                SctInput::Input3 => (*swm).pinassign6.modify(|_, w| w.ctin_3_i().bits(pin)),
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn set_input1_enabled(enabled: bool) {
+        let swm = lpc81x_pac::SWM::ptr();
+        unsafe {
+            (*swm).pinenable0.modify(|_, w| {
+                if enabled {
+                    w.acmp_i1_en().enable_acmp_i1_this()
+                } else {
+                    w.acmp_i1_en().disable_acmp_i1_gpi()
+                }
+            })
+        }
+    }
+
+    #[inline(always)]
+    fn set_input2_enabled(enabled: bool) {
+        let swm = lpc81x_pac::SWM::ptr();
+        unsafe {
+            (*swm).pinenable0.modify(|_, w| {
+                if enabled {
+                    w.acmp_i2_en().enable_acmp_i2_this()
+                } else {
+                    w.acmp_i2_en().disable_acmp_i2_gpi()
+                }
+            })
+        }
+    }
+
+    #[inline(always)]
+    fn set_powered(powered: bool) {
+        let syscon = lpc81x_pac::SYSCON::ptr();
+        unsafe {
+            if powered {
+                (*syscon).pdruncfg.modify(|_, w| w.acmp().powered());
+                (*syscon).sysahbclkctrl.modify(|_, w| w.acmp().enable());
+                cortex_m::asm::dsb();
+                (*syscon)
+                    .presetctrl
+                    .modify(|_, w| w.acmp_rst_n().clear_the_analog_com());
+            } else {
+                (*syscon)
+                    .presetctrl
+                    .modify(|_, w| w.acmp_rst_n().assert_the_analog_co());
+                cortex_m::asm::dsb();
+                (*syscon).sysahbclkctrl.modify(|_, w| w.acmp().disable());
+                (*syscon).pdruncfg.modify(|_, w| w.acmp().powered_down());
+            }
+        }
+        cortex_m::asm::dsb();
+    }
+
+    #[inline(always)]
+    fn configure(cfg: cfg::Config) {
+        let periph = lpc81x_pac::CMP::ptr();
+        unsafe {
+            (*periph).ctrl.modify(|_, w| {
+                match cfg.positive {
+                    cfg::Input::VoltageLadder => w.comp_vp_sel().voltage_ladder_outpu(),
+                    cfg::Input::Input1 => w.comp_vp_sel().acmp_i1(),
+                    cfg::Input::Input2 => w.comp_vp_sel().acmp_i2(),
+                    cfg::Input::Bandgap => w.comp_vp_sel().internal_reference_v(),
+                };
+                match cfg.negative {
+                    cfg::Input::VoltageLadder => w.comp_vm_sel().voltage_ladder_outpu(),
+                    cfg::Input::Input1 => w.comp_vm_sel().acmp_i1(),
+                    cfg::Input::Input2 => w.comp_vm_sel().acmp_i2(),
+                    cfg::Input::Bandgap => w.comp_vm_sel().internal_reference_v(),
+                };
+                match cfg.hysteresis {
+                    cfg::Hysteresis::None => w.hys().none_the_output_wil(),
+                    cfg::Hysteresis::MilliVolts5 => w.hys()._5_mv(),
+                    cfg::Hysteresis::MilliVolts10 => w.hys()._10_mv(),
+                    cfg::Hysteresis::MilliVolts20 => w.hys()._20_mv(),
+                };
+                match cfg.edge {
+                    cfg::Edge::Falling => w.edgesel().falling_edges(),
+                    cfg::Edge::Rising => w.edgesel().rising_edges(),
+                    cfg::Edge::Both => w.edgesel().both_edges_2(),
+                };
+                match cfg.output {
+                    cfg::OutputMode::Direct => w.compsa().direct(),
+                    cfg::OutputMode::Synchronized => w.compsa().synch(),
+                }
+            })
+        }
+    }
+}
+
+/* ******************************
+    METHODS FOR INACTIVE MODE
+****************************** */
+
+impl CMP<mode::Inactive, pins::mode::Unassigned, pins::mode::Unassigned, pins::mode::Unassigned> {
+    /// Consumes the inactive comparator and returns it powered up and
+    /// configured as described by the given configuration.
+    ///
+    /// The external inputs are not yet connected. If the configuration
+    /// selects `Input1` or `Input2` then call `with_input1` or `with_input2`
+    /// respectively to connect the corresponding pin.
+    pub fn activate(
+        self,
+        cfg: cfg::Config,
+    ) -> CMP<mode::Active, pins::mode::Unassigned, pins::mode::Unassigned, pins::mode::Unassigned>
+    {
+        Self::set_powered(true);
+        Self::configure(cfg);
+        CMP::new()
+    }
+}
+
+/* ******************************
+    METHODS FOR ACTIVE MODE
+****************************** */
+
+impl<I1, I2, OUT> CMP<mode::Active, I1, I2, OUT>
+where
+    I1: pins::PinAssignment,
+    I2: pins::PinAssignment,
+    OUT: pins::PinAssignment,
+{
+    /// Replaces the comparator configuration.
+    ///
+    /// Changing the input selections may cause a spurious edge to be
+    /// detected, so callers using edge detection should call
+    /// `acknowledge_edge` afterwards.
+    pub fn reconfigure(&mut self, cfg: cfg::Config) {
+        Self::configure(cfg);
+    }
+
+    /// Returns the current state of the comparator output, which is `true`
+    /// when the voltage at the positive input is greater than the voltage at
+    /// the negative input.
+    #[inline(always)]
+    pub fn output(&self) -> bool {
+        let periph = lpc81x_pac::CMP::ptr();
+        unsafe { (*periph).ctrl.read().compstat().bit_is_set() }
+    }
+
+    /// Returns true if an edge of the type selected in the configuration
+    /// has been detected since the last call to `acknowledge_edge`.
+    #[inline(always)]
+    pub fn edge_detected(&self) -> bool {
+        let periph = lpc81x_pac::CMP::ptr();
+        unsafe { (*periph).ctrl.read().compedge().bit_is_set() }
+    }
+
+    /// Clears the edge detection flag.
+    ///
+    /// The comparator interrupt service routine must call this before
+    /// returning or else it will be immediately called again as soon as it
+    /// returns.
+    #[inline(always)]
+    pub fn acknowledge_edge(&self) {
+        let periph = lpc81x_pac::CMP::ptr();
+        unsafe {
+            (*periph).ctrl.modify(|_, w| w.edgeclr().set_bit());
+            (*periph).ctrl.modify(|_, w| w.edgeclr().clear_bit());
+        }
+    }
+
+    /// Enables the voltage ladder and selects its output voltage.
+    ///
+    /// The ladder divides the supply voltage VDD into 32 steps, so the
+    /// resulting voltage is `VDD * step / 31`. Only the low five bits of
+    /// `step` are used. The ladder output can be selected as either of the
+    /// comparator inputs using `cfg::Input::VoltageLadder`.
+    ///
+    /// After changing the ladder step, the ladder output needs some time to
+    /// settle before the comparator output is reliable. See the LPC81x
+    /// datasheet for the settling time.
+    pub fn set_ladder(&mut self, step: u8) {
+        let periph = lpc81x_pac::CMP::ptr();
+        unsafe {
+            (*periph).lad.write(|w| {
+                w.ladsel()
+                    .bits(step & 0b11111)
+                    .laden()
+                    .set_bit()
+                    .ladref()
+                    .supply_pin_vdd()
+            })
+        }
+    }
+
+    /// Disables the voltage ladder to save power.
+    pub fn disable_ladder(&mut self) {
+        let periph = lpc81x_pac::CMP::ptr();
+        unsafe { (*periph).lad.write(|w| w.laden().clear_bit()) }
+    }
+}
+
+impl<I2, OUT> CMP<mode::Active, pins::mode::Unassigned, I2, OUT>
+where
+    I2: pins::PinAssignment,
+    OUT: pins::PinAssignment,
+{
+    /// Connects GPIO pin 0 to the comparator's ACMP_I1 input.
+    ///
+    /// While ACMP_I1 is active, all digital input functions on this pin will
+    /// read consistently low.
+    pub fn with_input1(
+        self,
+        pin: pins::pin::Pin0<pins::mode::Unassigned>,
+    ) -> CMP<mode::Active, pins::mode::Assigned<pins::pin::Pin0<pins::mode::Unassigned>>, I2, OUT>
+    {
+        Self::set_input1_enabled(true);
+        unused(pin);
+        CMP::new()
+    }
+}
+
+impl<I1, OUT> CMP<mode::Active, I1, pins::mode::Unassigned, OUT>
+where
+    I1: pins::PinAssignment,
+    OUT: pins::PinAssignment,
+{
+    /// Connects GPIO pin 1 to the comparator's ACMP_I2 input.
+    ///
+    /// While ACMP_I2 is active, all digital input functions on this pin will
+    /// read consistently low.
+    pub fn with_input2(
+        self,
+        pin: pins::pin::Pin1<pins::mode::Unassigned>,
+    ) -> CMP<mode::Active, I1, pins::mode::Assigned<pins::pin::Pin1<pins::mode::Unassigned>>, OUT>
+    {
+        Self::set_input2_enabled(true);
+        unused(pin);
+        CMP::new()
+    }
+}
+
+impl<I1, I2> CMP<mode::Active, I1, I2, pins::mode::Unassigned>
+where
+    I1: pins::PinAssignment,
+    I2: pins::PinAssignment,
+{
+    /// Assigns an unassigned external pin to the comparator output signal
+    /// ACMP_O.
+    pub fn with_output<OUT: pins::UnassignedPin>(
+        self,
+        out: OUT,
+    ) -> CMP<mode::Active, I1, I2, pins::mode::Assigned<OUT>> {
+        Self::select_output(OUT::NUMBER);
+        unused(out);
+        CMP::new()
+    }
+}
+
+impl<I1, I2, OUT> CMP<mode::Active, I1, I2, pins::mode::Assigned<OUT>>
+where
+    I1: pins::PinAssignment,
+    I2: pins::PinAssignment,
+    OUT: pins::Pin,
+{
+    /// Connects the comparator output to one of the state configurable timer
+    /// (SCT) inputs, so that comparator transitions can be used as SCT
+    /// events.
+    ///
+    /// The LPC81x has no internal connection between the comparator and the
+    /// SCT, so this works by assigning the SCT input to the same pin as the
+    /// comparator output via the switch matrix. For example, the SCT can then
+    /// use a comparator input crossing a current-sense threshold to end a
+    /// PWM cycle early, giving cycle-by-cycle current limiting without any
+    /// software involvement.
+    ///
+    /// The SCT input remains connected until `disconnect_from_sct` is called
+    /// for the same input, or until the output pin is released.
+    pub fn connect_to_sct(&mut self, input: SctInput) {
+        Self::select_sct_input(input, OUT::NUMBER);
+    }
+
+    /// Disconnects the given SCT input from the comparator output, if it
+    /// was previously connected using `connect_to_sct`.
+    pub fn disconnect_from_sct(&mut self, input: SctInput) {
+        Self::select_sct_input(input, pins::PINASSIGN_NOTHING);
+    }
+
+    /// Consumes the comparator object and returns a new object with the
+    /// output pin detached.
+    ///
+    /// Any SCT inputs previously connected with `connect_to_sct` are
+    /// disconnected too. Along with that new object, the former output pin
+    /// is also returned in unassigned mode, ready to be assigned to another
+    /// function.
+    pub fn release_output(self) -> (CMP<mode::Active, I1, I2, pins::mode::Unassigned>, OUT) {
+        let swm = lpc81x_pac::SWM::ptr();
+        let r6 = unsafe { (*swm).pinassign6.read() };
+        let r5 = unsafe { (*swm).pinassign5.read() };
+        if r5.ctin_0_i().bits() == OUT::NUMBER {
+            Self::select_sct_input(SctInput::Input0, pins::PINASSIGN_NOTHING);
+        }
+        if r6.ctin_1_i().bits() == OUT::NUMBER {
+            Self::select_sct_input(SctInput::Input1, pins::PINASSIGN_NOTHING);
+        }
+        if r6.ctin_2_i().bits() == OUT::NUMBER {
+            Self::select_sct_input(SctInput::Input2, pins::PINASSIGN_NOTHING);
+        }
+        if r6.ctin_3_i().bits() == OUT::NUMBER {
+            Self::select_sct_input(SctInput::Input3, pins::PINASSIGN_NOTHING);
+        }
+        Self::select_output(pins::PINASSIGN_NOTHING);
+        (CMP::new(), pin_type_as_is())
+    }
+}
+
+impl<I2, OUT>
+    CMP<mode::Active, pins::mode::Assigned<pins::pin::Pin0<pins::mode::Unassigned>>, I2, OUT>
+where
+    I2: pins::PinAssignment,
+    OUT: pins::PinAssignment,
+{
+    /// Consumes the comparator object and returns a new object with GPIO
+    /// pin 0 disconnected from ACMP_I1, along with the pin itself.
+    pub fn release_input1(
+        self,
+    ) -> (
+        CMP<mode::Active, pins::mode::Unassigned, I2, OUT>,
+        pins::pin::Pin0<pins::mode::Unassigned>,
+    ) {
+        Self::set_input1_enabled(false);
+        (CMP::new(), pin_type_as_is())
+    }
+}
+
+impl<I1, OUT>
+    CMP<mode::Active, I1, pins::mode::Assigned<pins::pin::Pin1<pins::mode::Unassigned>>, OUT>
+where
+    I1: pins::PinAssignment,
+    OUT: pins::PinAssignment,
+{
+    /// Consumes the comparator object and returns a new object with GPIO
+    /// pin 1 disconnected from ACMP_I2, along with the pin itself.
+    pub fn release_input2(
+        self,
+    ) -> (
+        CMP<mode::Active, I1, pins::mode::Unassigned, OUT>,
+        pins::pin::Pin1<pins::mode::Unassigned>,
+    ) {
+        Self::set_input2_enabled(false);
+        (CMP::new(), pin_type_as_is())
+    }
+}
+
+impl CMP<mode::Active, pins::mode::Unassigned, pins::mode::Unassigned, pins::mode::Unassigned> {
+    /// Consumes the active comparator and returns it powered down.
+    ///
+    /// This method can be called only when the input and output pins have
+    /// already been released.
+    pub fn deactivate(
+        self,
+    ) -> CMP<mode::Inactive, pins::mode::Unassigned, pins::mode::Unassigned, pins::mode::Unassigned>
+    {
+        Self::configure(cfg::RESET_CONFIG);
+        let periph = lpc81x_pac::CMP::ptr();
+        unsafe { (*periph).lad.write(|w| w) }
+        Self::set_powered(false);
+        CMP::new()
+    }
+}
+
+/// Identifies one of the four state configurable timer (SCT) inputs.
+#[derive(Clone, Copy)]
+pub enum SctInput {
+    Input0,
+    Input1,
+    Input2,
+    Input3,
+}
+
+// Represents comparator modes.
+//
+// Can be safely implemented only by types in this crate.
+pub unsafe trait Mode {}
+
+#[inline(always)]
+fn unused<T>(_v: T) {}
+
+// Helper function for creating "instances" of our zero-length pin types
+// without needing to state their names, when we're releasing/deactivating
+// pins.
+#[inline(always)]
+fn pin_type_as_is<T: pins::Pin>() -> T {
+    // This is safe because our pin types are zero-length anyway, and so
+    // "filling them with zeroes" is indistinguishable from properly
+    // initializing them.
+    unsafe { core::mem::zeroed() }
+}
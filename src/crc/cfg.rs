@@ -0,0 +1,70 @@
+/// CRC-CCITT as commonly used by XMODEM derivatives and many serial
+/// protocols, also known as CRC-16/CCITT-FALSE.
+///
+/// The checksum of the ASCII string "123456789" is `0x29B1`.
+pub const CRC_CCITT: Config = Config {
+    polynomial: Polynomial::CCITT,
+    seed: 0xffff,
+    reverse_input: false,
+    complement_input: false,
+    reverse_output: false,
+    complement_output: false,
+};
+
+/// CRC-16 with the parameters often called CRC-16/ARC, as used by Modbus
+/// (with a different seed) and many other protocols.
+///
+/// The checksum of the ASCII string "123456789" is `0xBB3D`.
+pub const CRC_16: Config = Config {
+    polynomial: Polynomial::CRC16,
+    seed: 0x0000,
+    reverse_input: true,
+    complement_input: false,
+    reverse_output: true,
+    complement_output: false,
+};
+
+/// CRC-32 as used by Ethernet, zlib, PNG, and many other formats.
+///
+/// The checksum of the ASCII string "123456789" is `0xCBF43926`.
+pub const CRC_32: Config = Config {
+    polynomial: Polynomial::CRC32,
+    seed: 0xffffffff,
+    reverse_input: true,
+    complement_input: false,
+    reverse_output: true,
+    complement_output: true,
+};
+
+pub struct Config {
+    pub polynomial: Polynomial,
+
+    /// The initial value of the checksum before any data is written.
+    pub seed: u32,
+
+    /// If set, the bit order of each byte of input data is reversed before
+    /// it is processed.
+    pub reverse_input: bool,
+
+    /// If set, each item of input data is complemented before it is
+    /// processed.
+    pub complement_input: bool,
+
+    /// If set, the bit order of the checksum is reversed when it is read.
+    pub reverse_output: bool,
+
+    /// If set, the checksum is complemented when it is read.
+    pub complement_output: bool,
+}
+
+/// Selects one of the CRC polynomials supported by the hardware.
+pub enum Polynomial {
+    /// The CRC-CCITT polynomial, x^16 + x^12 + x^5 + 1 (0x1021).
+    CCITT,
+
+    /// The CRC-16 polynomial, x^16 + x^15 + x^2 + 1 (0x8005).
+    CRC16,
+
+    /// The CRC-32 polynomial, 0x04C11DB7.
+    CRC32,
+}
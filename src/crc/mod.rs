@@ -0,0 +1,175 @@
+//! Interface to the CRC engine.
+//!
+//! The CRC engine computes CRC-CCITT, CRC-16, or CRC-32 checksums in
+//! hardware, avoiding the flash cost of table-based software
+//! implementations. Presets for the most common parameter sets are in
+//! `cfg`.
+//!
+//! ```rust
+//! use core::hash::Hasher;
+//!
+//! let mut crc = p.crc.activate(hal::crc::cfg::CRC_32);
+//! crc.write(b"123456789");
+//! assert_eq!(crc.sum(), 0xCBF43926);
+//! ```
+
+use core::marker::PhantomData;
+
+pub mod cfg;
+pub mod mode;
+
+/// Represents the CRC engine.
+///
+/// The CRC engine starts in an inactive state. Call `activate` to enable its
+/// clock and prepare it to compute a checksum with a particular
+/// configuration.
+///
+/// An active CRC engine implements `core::hash::Hasher`, which feeds data one
+/// byte at a time. Use `write_halfword` and `write_word` to feed larger
+/// units directly in fewer bus writes.
+pub struct CRC<MODE: Mode>(PhantomData<MODE>);
+
+/// The CRC object represents access to a single system peripheral, so it's
+/// not safe to share it across multiple threads without some external
+/// concurrency control mechanisms.
+impl<MODE: Mode> !Sync for CRC<MODE> {}
+
+impl<MODE: Mode> CRC<MODE> {
+    #[inline(always)]
+    pub(crate) fn new() -> Self {
+        Self(PhantomData)
+    }
+
+    #[inline(always)]
+    fn set_crc_clock(active: bool) {
+        let syscon = lpc81x_pac::SYSCON::ptr();
+        unsafe {
+            (*syscon).sysahbclkctrl.modify(|_, w| {
+                if active {
+                    w.crc().enable()
+                } else {
+                    w.crc().disable()
+                }
+            });
+        }
+        cortex_m::asm::dsb();
+    }
+
+    // The data register shares its address with the (read-only) sum register,
+    // so the PAC doesn't expose it as part of the register block. The
+    // hardware accepts 8-, 16-, and 32-bit writes to it, with each write
+    // size being processed as a unit of that size.
+    #[inline(always)]
+    fn wr_data<T>() -> *mut T {
+        let periph = lpc81x_pac::CRC::ptr();
+        unsafe { (periph as *mut u8).add(8) as *mut T }
+    }
+}
+
+impl CRC<mode::Inactive> {
+    /// Consumes the inactive CRC engine and returns it active and ready to
+    /// compute a checksum using the given configuration.
+    pub fn activate(self, cfg: cfg::Config) -> CRC<mode::Active> {
+        Self::set_crc_clock(true);
+        let mut ret = CRC::new();
+        ret.start(cfg);
+        ret
+    }
+}
+
+impl CRC<mode::Active> {
+    /// Begins a new checksum using the given configuration, discarding any
+    /// checksum already in progress.
+    pub fn start(&mut self, cfg: cfg::Config) {
+        let periph = lpc81x_pac::CRC::ptr();
+        unsafe {
+            // The mode must be set first, because writing the seed applies
+            // the selected output bit order and complement settings.
+            (*periph).mode.write(|w| {
+                match cfg.polynomial {
+                    cfg::Polynomial::CCITT => w.crc_poly().bits(0b00),
+                    cfg::Polynomial::CRC16 => w.crc_poly().bits(0b01),
+                    cfg::Polynomial::CRC32 => w.crc_poly().bits(0b10),
+                };
+                w.bit_rvs_wr()
+                    .bit(cfg.reverse_input)
+                    .cmpl_wr()
+                    .bit(cfg.complement_input)
+                    .bit_rvs_sum()
+                    .bit(cfg.reverse_output)
+                    .cmpl_sum()
+                    .bit(cfg.complement_output)
+            });
+            (*periph).seed.write(|w| w.crc_seed().bits(cfg.seed));
+        }
+    }
+
+    /// Returns the checksum of all of the data written since the last call
+    /// to `start` or `activate`.
+    ///
+    /// For the 16-bit polynomials, only the low 16 bits are significant.
+    #[inline(always)]
+    pub fn sum(&self) -> u32 {
+        let periph = lpc81x_pac::CRC::ptr();
+        unsafe { (*periph).sum.read().bits() }
+    }
+
+    /// Feeds a single byte into the checksum.
+    #[inline(always)]
+    pub fn write_u8(&mut self, v: u8) {
+        unsafe { core::ptr::write_volatile(Self::wr_data::<u8>(), v) }
+    }
+
+    /// Feeds a 16-bit halfword into the checksum as a single unit.
+    ///
+    /// Input bit reversal (if enabled) applies separately to each byte. The
+    /// result is not the same as `Hasher::write_u16`, which feeds the bytes
+    /// of the value one at a time in little-endian order.
+    #[inline(always)]
+    pub fn write_halfword(&mut self, v: u16) {
+        unsafe { core::ptr::write_volatile(Self::wr_data::<u16>(), v) }
+    }
+
+    /// Feeds a 32-bit word into the checksum as a single unit.
+    ///
+    /// Input bit reversal (if enabled) applies separately to each byte. The
+    /// result is not the same as `Hasher::write_u32`, which feeds the bytes
+    /// of the value one at a time in little-endian order.
+    #[inline(always)]
+    pub fn write_word(&mut self, v: u32) {
+        unsafe { core::ptr::write_volatile(Self::wr_data::<u32>(), v) }
+    }
+
+    /// Consumes the active CRC engine and returns it inactive.
+    pub fn deactivate(self) -> CRC<mode::Inactive> {
+        let periph = lpc81x_pac::CRC::ptr();
+        unsafe {
+            (*periph).mode.write(|w| w); // Set back to the reset value
+        }
+        Self::set_crc_clock(false);
+        CRC::new()
+    }
+}
+
+impl core::hash::Hasher for CRC<mode::Active> {
+    /// Feeds the given bytes into the checksum, one byte at a time.
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.write_u8(*b);
+        }
+    }
+
+    fn write_u8(&mut self, v: u8) {
+        CRC::write_u8(self, v);
+    }
+
+    /// Returns the current checksum, as would be returned by `sum`.
+    fn finish(&self) -> u64 {
+        self.sum() as u64
+    }
+}
+
+// Represents CRC engine modes.
+//
+// Can be safely implemented only by types in this crate.
+pub unsafe trait Mode {}
@@ -0,0 +1,5 @@
+pub enum Inactive {}
+unsafe impl super::Mode for Inactive {}
+
+pub enum Active {}
+unsafe impl super::Mode for Active {}
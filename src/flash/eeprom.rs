@@ -0,0 +1,457 @@
+//! EEPROM emulation using a reserved region of flash.
+//!
+//! `Store` is a small key/value store for persistent settings such as
+//! calibration constants. Each value is saved as a record occupying one
+//! flash page, and records are appended to the reserved region as a circular
+//! log so that erasing is spread evenly over all of its sectors. A value is
+//! updated by appending a new record for the same key, and reading a key
+//! returns the record with the highest sequence number.
+//!
+//! The region must be reserved in `memory.x` by shrinking the `FLASH` region
+//! and defining the symbols `_eeprom_start` and `_eeprom_end` to delimit the
+//! reserved flash. The region must consist of at least two whole sectors:
+//!
+//! ```text
+//! MEMORY
+//! {
+//!   FLASH : ORIGIN = 0x00000000, LENGTH = 14K
+//!   EEPROM : ORIGIN = 0x00003800, LENGTH = 2K
+//!   RAM : ORIGIN = 0x10000000, LENGTH = 4K
+//! }
+//! _eeprom_start = ORIGIN(EEPROM);
+//! _eeprom_end = ORIGIN(EEPROM) + LENGTH(EEPROM);
+//! ```
+//!
+//! One sector of the region is always kept erased. When the log reaches
+//! that sector, any records in the following (oldest) sector that are still
+//! current are copied forward before that sector is erased to become the new
+//! spare. Each record is protected by a CRC-32, computed using the CRC
+//! engine, and copies are always written before the originals are erased, so
+//! a loss of power at any point leaves either the old or the new value of
+//! every key readable.
+//!
+//! ```rust
+//! let clocks = p.clock.freeze();
+//! let mut store = hal::flash::eeprom::Store::open(p.flash, p.crc, &clocks).unwrap();
+//! store.write(1, &[0x12, 0x34]).unwrap();
+//! if let Some(v) = store.read(1) {
+//!     assert_eq!(v.bytes(), &[0x12, 0x34]);
+//! }
+//! ```
+
+use super::{Flash, IapError, Page, PAGES_PER_SECTOR, PAGE_SIZE, SECTOR_SIZE};
+use crate::clock;
+use crate::crc::{self, CRC};
+
+/// The maximum length in bytes of a single value.
+pub const MAX_VALUE_LEN: usize = PAGE_SIZE - HEADER_LEN - CRC_LEN;
+
+// Each record page begins with a header:
+//     bytes 0-3: sequence number, little-endian
+//     byte 4: key
+//     byte 5: value length
+//     byte 6: kind (KIND_VALUE or KIND_REMOVED)
+//     byte 7: reserved
+// The value follows, and the last four bytes are a CRC-32 of everything
+// before them.
+const HEADER_LEN: usize = 8;
+const CRC_LEN: usize = 4;
+const KIND_VALUE: u8 = 0x01;
+const KIND_REMOVED: u8 = 0x00;
+
+extern "C" {
+    static _eeprom_start: u8;
+    static _eeprom_end: u8;
+}
+
+/// A key/value store in the flash region reserved in `memory.x`.
+pub struct Store {
+    flash: Flash,
+    crc: CRC<crc::mode::Active>,
+    clocks: clock::Clocks,
+    first_page: u32,
+    pages: u32,
+
+    // The index within the region of the next page to try to write, and the
+    // sequence number to use for the next record. Records are always
+    // written in sequence order at the head, so walking backwards from the
+    // head visits valid records from newest to oldest.
+    head: u32,
+    next_seq: u32,
+}
+
+impl Store {
+    /// Takes ownership of the flash controller and the CRC engine, which is
+    /// used to check records, and opens the store in the region reserved in
+    /// `memory.x`.
+    ///
+    /// If the region contains no valid records, it is erased. If a previous
+    /// write or erase was interrupted, the store completes any unfinished
+    /// housekeeping before returning.
+    pub fn open(
+        flash: Flash,
+        crc: CRC<crc::mode::Inactive>,
+        clocks: &clock::Clocks,
+    ) -> Result<Self, Error> {
+        let (start, end) = unsafe {
+            (
+                &_eeprom_start as *const u8 as u32,
+                &_eeprom_end as *const u8 as u32,
+            )
+        };
+        let sector_size = SECTOR_SIZE as u32;
+        if start % sector_size != 0 || end % sector_size != 0 || end < start + 2 * sector_size {
+            return Err(Error::InvalidRegion);
+        }
+
+        let mut store = Self {
+            flash,
+            crc: crc.activate(crc::cfg::CRC_32),
+            clocks: *clocks,
+            first_page: start / (PAGE_SIZE as u32),
+            pages: (end - start) / (PAGE_SIZE as u32),
+            head: 0,
+            next_seq: 0,
+        };
+
+        let mut newest: Option<(u32, u32)> = None;
+        for idx in 0..store.pages {
+            if let Some(rec) = store.record(idx) {
+                match newest {
+                    Some((_, seq)) if seq >= rec.seq() => {}
+                    _ => newest = Some((idx, rec.seq())),
+                }
+            }
+        }
+        match newest {
+            Some((idx, seq)) => {
+                store.head = (idx + 1) % store.pages;
+                store.next_seq = seq + 1;
+            }
+            None => {
+                for sector in 0..store.sectors() {
+                    if !store.sector_blank(sector)? {
+                        store.erase_sector(sector)?;
+                    }
+                }
+            }
+        }
+        store.prepare_head()?;
+        Ok(store)
+    }
+
+    /// Closes the store and returns the flash controller and the CRC
+    /// engine.
+    pub fn release(self) -> (Flash, CRC<crc::mode::Inactive>) {
+        (self.flash, self.crc.deactivate())
+    }
+
+    /// Returns the maximum number of keys that can have values at once.
+    ///
+    /// One sector is always kept as an erased spare, and at least one page
+    /// must remain free to allow the oldest sector to be reclaimed.
+    pub fn capacity(&self) -> usize {
+        (self.pages - PAGES_PER_SECTOR - 1) as usize
+    }
+
+    /// Returns the current value of the given key, or `None` if it has no
+    /// value.
+    pub fn read(&mut self, key: u8) -> Option<Value> {
+        match self.latest(key) {
+            Some((_, rec)) if rec.kind() == KIND_VALUE => Some(Value(rec)),
+            _ => None,
+        }
+    }
+
+    /// Sets the value of the given key.
+    ///
+    /// If the key already has the same value then nothing is written, to
+    /// avoid unnecessary wear.
+    pub fn write(&mut self, key: u8, value: &[u8]) -> Result<(), Error> {
+        if value.len() > MAX_VALUE_LEN {
+            return Err(Error::TooLong);
+        }
+        match self.latest(key) {
+            Some((_, rec)) if rec.kind() == KIND_VALUE => {
+                if rec.value() == value {
+                    return Ok(());
+                }
+            }
+            _ => {
+                if self.count_values() >= self.capacity() {
+                    return Err(Error::Full);
+                }
+            }
+        }
+        self.append(Record::new(key, KIND_VALUE, value))
+    }
+
+    /// Removes any value for the given key.
+    pub fn remove(&mut self, key: u8) -> Result<(), Error> {
+        match self.latest(key) {
+            Some((_, rec)) if rec.kind() == KIND_VALUE => {
+                self.append(Record::new(key, KIND_REMOVED, &[]))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Appends a record at the head of the log, reclaiming the oldest sector
+    // first if necessary.
+    fn append(&mut self, mut rec: Record) -> Result<(), Error> {
+        loop {
+            self.prepare_head()?;
+            match self.program_in_sector(&mut rec) {
+                // The head has moved on to the next sector, which must now
+                // be prepared in turn.
+                Err(Error::Full) => continue,
+                result => return result,
+            }
+        }
+    }
+
+    // Ensures that the sector containing the head is ready for writing and
+    // that the sector after it is erased.
+    fn prepare_head(&mut self) -> Result<(), Error> {
+        loop {
+            let sector = self.head / PAGES_PER_SECTOR;
+            if self.head % PAGES_PER_SECTOR == 0 && !self.sector_blank(sector)? {
+                // This sector was the spare, so it can contain data only if
+                // an erase was interrupted after its records were copied
+                // forward.
+                self.erase_sector(sector)?;
+            }
+            self.ensure_spare()?;
+            if self.head / PAGES_PER_SECTOR == sector {
+                return Ok(());
+            }
+            // Copying records forward filled the current sector, so the
+            // head is now at the start of the former spare.
+        }
+    }
+
+    // Ensures that the sector after the one containing the head is erased,
+    // first copying forward any of its records that are still current.
+    fn ensure_spare(&mut self) -> Result<(), Error> {
+        let spare = (self.head / PAGES_PER_SECTOR + 1) % self.sectors();
+        if self.sector_blank(spare)? {
+            return Ok(());
+        }
+        let head_sector = self.head / PAGES_PER_SECTOR;
+        let first = spare * PAGES_PER_SECTOR;
+        let mut idx = first;
+        while idx < first + PAGES_PER_SECTOR {
+            if let Some(mut rec) = self.record(idx) {
+                // Removal records need not be kept, because any older values
+                // they hide are in this same sector.
+                if rec.kind() == KIND_VALUE && self.is_latest(idx, &rec) {
+                    match self.program_in_sector(&mut rec) {
+                        Ok(()) => {}
+                        Err(Error::Full) => {
+                            // A copy-forward was interrupted earlier, leaving
+                            // a torn page that takes a slot needed for a
+                            // copy. The head sector holds nothing but copies
+                            // of records that are still in the spare, so
+                            // erasing it makes the originals current again
+                            // and we can start over with every page free.
+                            self.erase_sector(head_sector)?;
+                            self.head = head_sector * PAGES_PER_SECTOR;
+                            idx = first;
+                            continue;
+                        }
+                        Err(err) => return Err(err),
+                    }
+                }
+            }
+            idx += 1;
+        }
+        self.erase_sector(spare)
+    }
+
+    // Programs the given record at the first blank page at or after the
+    // head within the head's current sector, returning `Error::Full` if
+    // there is none.
+    fn program_in_sector(&mut self, rec: &mut Record) -> Result<(), Error> {
+        let sector = self.head / PAGES_PER_SECTOR;
+        while self.head / PAGES_PER_SECTOR == sector {
+            let idx = self.head;
+            self.head = (self.head + 1) % self.pages;
+            if self.page_blank(idx) {
+                rec.seal(&mut self.crc, self.next_seq);
+                self.next_seq += 1;
+                self.flash
+                    .program_page(&self.clocks, self.first_page + idx, &rec.0)?;
+                return Ok(());
+            }
+        }
+        Err(Error::Full)
+    }
+
+    fn latest(&mut self, key: u8) -> Option<(u32, Record)> {
+        for n in 1..=self.pages {
+            let idx = self.newest_first(n);
+            if let Some(rec) = self.record(idx) {
+                if rec.key() == key {
+                    return Some((idx, rec));
+                }
+            }
+        }
+        None
+    }
+
+    fn is_latest(&mut self, idx: u32, rec: &Record) -> bool {
+        match self.latest(rec.key()) {
+            Some((latest_idx, _)) => latest_idx == idx,
+            None => false,
+        }
+    }
+
+    // Counts the keys that have values, in a single pass from newest to
+    // oldest so that only the first record seen for each key matters.
+    fn count_values(&mut self) -> usize {
+        let mut seen = [0u32; 8];
+        let mut count = 0;
+        for n in 1..=self.pages {
+            let idx = self.newest_first(n);
+            if let Some(rec) = self.record(idx) {
+                let (word, bit) = ((rec.key() / 32) as usize, 1 << (rec.key() % 32));
+                if seen[word] & bit == 0 {
+                    seen[word] |= bit;
+                    if rec.kind() == KIND_VALUE {
+                        count += 1;
+                    }
+                }
+            }
+        }
+        count
+    }
+
+    // Returns the index of the nth page before the head, for n from 1 to
+    // the number of pages.
+    fn newest_first(&self, n: u32) -> u32 {
+        (self.head + self.pages - n) % self.pages
+    }
+
+    fn record(&mut self, idx: u32) -> Option<Record> {
+        let rec = Record(self.flash.read_page(self.first_page + idx));
+        if rec.is_valid(&mut self.crc) {
+            Some(rec)
+        } else {
+            None
+        }
+    }
+
+    fn page_blank(&self, idx: u32) -> bool {
+        let page = self.flash.read_page(self.first_page + idx);
+        page.0.iter().all(|b| *b == 0xff)
+    }
+
+    fn sectors(&self) -> u32 {
+        self.pages / PAGES_PER_SECTOR
+    }
+
+    fn sector_blank(&self, sector: u32) -> Result<bool, Error> {
+        let abs = self.first_page / PAGES_PER_SECTOR + sector;
+        Ok(self.flash.blank_check_sectors(abs, abs)?)
+    }
+
+    fn erase_sector(&mut self, sector: u32) -> Result<(), Error> {
+        let abs = self.first_page / PAGES_PER_SECTOR + sector;
+        self.flash.erase_sectors(&self.clocks, abs, abs)?;
+        Ok(())
+    }
+}
+
+/// A value read from a `Store`.
+pub struct Value(Record);
+
+impl Value {
+    /// Returns the bytes of the value.
+    pub fn bytes(&self) -> &[u8] {
+        self.0.value()
+    }
+}
+
+/// Errors returned by `Store`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The region reserved in `memory.x` is not made of at least two whole
+    /// sectors.
+    InvalidRegion,
+
+    /// The value is longer than `MAX_VALUE_LEN`.
+    TooLong,
+
+    /// The store already holds values for as many keys as its capacity.
+    Full,
+
+    /// An IAP routine returned an error.
+    Iap(IapError),
+}
+
+impl From<IapError> for Error {
+    fn from(err: IapError) -> Self {
+        Error::Iap(err)
+    }
+}
+
+struct Record(Page);
+
+impl Record {
+    fn new(key: u8, kind: u8, value: &[u8]) -> Self {
+        let mut page = Page::blank();
+        page.0[4] = key;
+        page.0[5] = value.len() as u8;
+        page.0[6] = kind;
+        page.0[HEADER_LEN..HEADER_LEN + value.len()].copy_from_slice(value);
+        Record(page)
+    }
+
+    // Sets the sequence number and updates the CRC accordingly.
+    fn seal(&mut self, engine: &mut CRC<crc::mode::Active>, seq: u32) {
+        let bytes = &mut (self.0).0;
+        bytes[0..4].copy_from_slice(&seq.to_le_bytes());
+        let crc = crc32(engine, &bytes[..PAGE_SIZE - CRC_LEN]);
+        bytes[PAGE_SIZE - CRC_LEN..].copy_from_slice(&crc.to_le_bytes());
+    }
+
+    fn is_valid(&self, engine: &mut CRC<crc::mode::Active>) -> bool {
+        let bytes = self.bytes();
+        let mut stored = [0u8; CRC_LEN];
+        stored.copy_from_slice(&bytes[PAGE_SIZE - CRC_LEN..]);
+        self.seq() != 0xffff_ffff
+            && (bytes[5] as usize) <= MAX_VALUE_LEN
+            && u32::from_le_bytes(stored) == crc32(engine, &bytes[..PAGE_SIZE - CRC_LEN])
+    }
+
+    fn bytes(&self) -> &[u8; PAGE_SIZE] {
+        &(self.0).0
+    }
+
+    fn seq(&self) -> u32 {
+        let mut raw = [0u8; 4];
+        raw.copy_from_slice(&self.bytes()[0..4]);
+        u32::from_le_bytes(raw)
+    }
+
+    fn key(&self) -> u8 {
+        self.bytes()[4]
+    }
+
+    fn kind(&self) -> u8 {
+        self.bytes()[6]
+    }
+
+    fn value(&self) -> &[u8] {
+        let len = self.bytes()[5] as usize;
+        &self.bytes()[HEADER_LEN..HEADER_LEN + len]
+    }
+}
+
+fn crc32(engine: &mut CRC<crc::mode::Active>, data: &[u8]) -> u32 {
+    engine.start(crc::cfg::CRC_32);
+    for b in data {
+        engine.write_u8(*b);
+    }
+    engine.sum()
+}
@@ -0,0 +1,34 @@
+// Low-level access to the in-application programming (IAP) entry point in
+// the boot ROM.
+
+use super::IapError;
+
+// The address of the IAP entry point, with the low bit set to select the
+// Thumb instruction set.
+const IAP_ENTRY: usize = 0x1fff_1ff1;
+
+pub(crate) const PREPARE_SECTORS: u32 = 50;
+pub(crate) const COPY_RAM_TO_FLASH: u32 = 51;
+pub(crate) const ERASE_SECTORS: u32 = 52;
+pub(crate) const BLANK_CHECK_SECTORS: u32 = 53;
+pub(crate) const READ_PART_ID: u32 = 54;
+pub(crate) const READ_BOOT_CODE_VERSION: u32 = 55;
+pub(crate) const COMPARE: u32 = 56;
+pub(crate) const READ_UID: u32 = 58;
+pub(crate) const ERASE_PAGES: u32 = 59;
+
+// Calls the IAP entry point with the given command table, returning the
+// result words that follow the status code if the command succeeds.
+//
+// Interrupts are disabled for the duration of the call, because flash is
+// not readable while a programming operation is in progress and so any
+// interrupt handler located in flash would fault.
+pub(crate) fn call(command: [u32; 5]) -> Result<[u32; 4], IapError> {
+    let mut result = [0u32; 5];
+    let entry: extern "C" fn(*const u32, *mut u32) = unsafe { core::mem::transmute(IAP_ENTRY) };
+    cortex_m::interrupt::free(|_| entry(command.as_ptr(), result.as_mut_ptr()));
+    match IapError::from_status(result[0]) {
+        Some(err) => Err(err),
+        None => Ok([result[1], result[2], result[3], result[4]]),
+    }
+}
@@ -0,0 +1,277 @@
+//! Flash programming using the in-application programming (IAP) routines in
+//! the boot ROM.
+//!
+//! The LPC81x flash is organized as 1KB sectors, each made of sixteen 64-byte
+//! pages. Erasing works on whole pages or sectors, and programming works on
+//! whole pages. Erased flash reads as all ones.
+//!
+//! ```rust
+//! let clocks = p.clock.freeze();
+//! let mut flash = p.flash;
+//! let mut page = hal::flash::Page::blank();
+//! page.0[..5].copy_from_slice(b"hello");
+//! flash.erase_pages(&clocks, 240, 240).unwrap();
+//! flash.program_page(&clocks, 240, &page).unwrap();
+//! ```
+//!
+//! The IAP routines use the top 32 bytes of RAM as working memory, so the
+//! stack must start below that. For `cortex-m-rt` applications, set
+//! `_stack_start` in `memory.x` accordingly:
+//!
+//! ```text
+//! _stack_start = ORIGIN(RAM) + LENGTH(RAM) - 32;
+//! ```
+//!
+//! Interrupts are disabled during each IAP call, because the flash cannot be
+//! read while it is being erased or programmed. Erasing a page or sector
+//! takes several milliseconds, so this can delay interrupt handling
+//! significantly.
+//!
+//! For storing small amounts of persistent data such as settings, the
+//! `eeprom` module provides a key/value store built on these routines.
+
+use crate::clock;
+use core::marker::PhantomData;
+
+pub mod eeprom;
+pub(crate) mod iap;
+
+/// The size of a flash page in bytes, which is the unit of programming and
+/// the smallest unit of erasing.
+pub const PAGE_SIZE: usize = 64;
+
+/// The size of a flash sector in bytes, which is the unit of write
+/// protection and the largest unit of erasing.
+pub const SECTOR_SIZE: usize = 1024;
+
+/// The number of pages in each sector.
+pub const PAGES_PER_SECTOR: u32 = (SECTOR_SIZE / PAGE_SIZE) as u32;
+
+/// Represents the flash memory controller, for erasing and programming the
+/// flash using the IAP routines.
+///
+/// Page and sector numbers count from the start of flash, so page `n` begins
+/// at address `n * PAGE_SIZE`. The available page and sector numbers depend
+/// on the flash size of the device model. Erasing or programming the flash
+/// that contains the running program will cause it to crash, so
+/// applications should reserve a separate region for their data in
+/// `memory.x`.
+pub struct Flash(PhantomData<()>);
+
+/// The Flash object represents access to a single system peripheral, so it's
+/// not safe to share it across multiple threads without some external
+/// concurrency control mechanisms.
+impl !Sync for Flash {}
+
+impl Flash {
+    pub(crate) fn new() -> Self {
+        Self(PhantomData)
+    }
+
+    /// Erases the given inclusive range of sectors, setting all of their
+    /// bytes to `0xff`.
+    pub fn erase_sectors(
+        &mut self,
+        clocks: &clock::Clocks,
+        first: u32,
+        last: u32,
+    ) -> Result<(), IapError> {
+        Self::prepare(first, last)?;
+        iap::call([iap::ERASE_SECTORS, first, last, cclk_khz(clocks), 0])?;
+        Ok(())
+    }
+
+    /// Erases the given inclusive range of pages, setting all of their bytes
+    /// to `0xff`.
+    pub fn erase_pages(
+        &mut self,
+        clocks: &clock::Clocks,
+        first: u32,
+        last: u32,
+    ) -> Result<(), IapError> {
+        Self::prepare(first / PAGES_PER_SECTOR, last / PAGES_PER_SECTOR)?;
+        iap::call([iap::ERASE_PAGES, first, last, cclk_khz(clocks), 0])?;
+        Ok(())
+    }
+
+    /// Programs the given page with the given data.
+    ///
+    /// Programming can only change bits from one to zero, so the page should
+    /// normally be erased first. The data must be located in RAM, or the
+    /// IAP routine will return `IapError::SrcAddrNotMapped`.
+    pub fn program_page(
+        &mut self,
+        clocks: &clock::Clocks,
+        page: u32,
+        data: &Page,
+    ) -> Result<(), IapError> {
+        Self::prepare(page / PAGES_PER_SECTOR, page / PAGES_PER_SECTOR)?;
+        iap::call([
+            iap::COPY_RAM_TO_FLASH,
+            page * (PAGE_SIZE as u32),
+            data as *const Page as u32,
+            PAGE_SIZE as u32,
+            cclk_khz(clocks),
+        ])?;
+        Ok(())
+    }
+
+    /// Returns the number of flash pages on the current device.
+    ///
+    /// If the device model is not one known to this library, this assumes
+    /// the largest flash size of the LPC81x family.
+    pub fn page_count(&self) -> u32 {
+        let size = match crate::Model::current() {
+            Some(model) => model.flash_size(),
+            None => 16 * 1024,
+        };
+        (size / PAGE_SIZE) as u32
+    }
+
+    /// Returns a copy of the current contents of the given page.
+    ///
+    /// Panics if the page number is not less than `page_count`.
+    pub fn read_page(&self, page: u32) -> Page {
+        assert!(page < self.page_count());
+        let addr = (page as usize) * PAGE_SIZE;
+        let mut ret = Page::blank();
+        for (i, chunk) in ret.0.chunks_mut(4).enumerate() {
+            chunk.copy_from_slice(&read_word(addr + i * 4).to_le_bytes());
+        }
+        ret
+    }
+
+    /// Returns true if all of the sectors in the given inclusive range are
+    /// blank, meaning that every byte is `0xff`.
+    pub fn blank_check_sectors(&self, first: u32, last: u32) -> Result<bool, IapError> {
+        match iap::call([iap::BLANK_CHECK_SECTORS, first, last, 0, 0]) {
+            Ok(_) => Ok(true),
+            Err(IapError::SectorNotBlank) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Returns true if the given page currently contains exactly the given
+    /// data.
+    pub fn compare_page(&self, page: u32, data: &Page) -> Result<bool, IapError> {
+        match iap::call([
+            iap::COMPARE,
+            page * (PAGE_SIZE as u32),
+            data as *const Page as u32,
+            PAGE_SIZE as u32,
+            0,
+        ]) {
+            Ok(_) => Ok(true),
+            Err(IapError::CompareError) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    // Every erase or program operation must be immediately preceded by
+    // preparing the sectors it affects.
+    fn prepare(first: u32, last: u32) -> Result<(), IapError> {
+        iap::call([iap::PREPARE_SECTORS, first, last, 0, 0])?;
+        Ok(())
+    }
+}
+
+// Reads the word at the given flash address.
+//
+// Rust considers any read through a null pointer to be undefined behavior,
+// even though the vector table lives at address zero, so that one word is
+// loaded by calling a tiny Thumb routine instead, in the same way that the
+// IAP routines are called through a function pointer.
+#[inline(always)]
+fn read_word(addr: usize) -> u32 {
+    if addr != 0 {
+        return unsafe { core::ptr::read_volatile(addr as *const u32) };
+    }
+    let entry: extern "C" fn(usize) -> u32 =
+        unsafe { core::mem::transmute((&LOAD_WORD as *const [u16; 2] as usize) | 1) };
+    entry(addr)
+}
+
+// Thumb machine code for a function that returns the word at the address
+// given in its first argument.
+static LOAD_WORD: [u16; 2] = [
+    0x6800, // ldr r0, [r0]
+    0x4770, // bx lr
+];
+
+/// The contents of one flash page.
+///
+/// The IAP routines require word-aligned buffers, which this type
+/// guarantees.
+#[derive(Clone, Copy)]
+#[repr(C, align(4))]
+pub struct Page(pub [u8; PAGE_SIZE]);
+
+impl Page {
+    /// Returns a page with all bytes set to `0xff`, matching erased flash.
+    pub const fn blank() -> Self {
+        Self([0xff; PAGE_SIZE])
+    }
+}
+
+/// Errors returned by the IAP routines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IapError {
+    InvalidCommand,
+    SrcAddrError,
+    DstAddrError,
+    SrcAddrNotMapped,
+    DstAddrNotMapped,
+    CountError,
+    InvalidSector,
+    SectorNotBlank,
+    SectorNotPrepared,
+    CompareError,
+    Busy,
+    ParamError,
+    AddrError,
+    AddrNotMapped,
+    CmdLocked,
+    InvalidCode,
+    InvalidBaudRate,
+    InvalidStopBit,
+    CodeReadProtectionEnabled,
+
+    /// A status code not documented in the LPC81x user manual.
+    Unknown(u32),
+}
+
+impl IapError {
+    // Returns the error for the given IAP status code, or None if the code
+    // indicates success.
+    pub(crate) fn from_status(status: u32) -> Option<Self> {
+        match status {
+            0 => None,
+            1 => Some(IapError::InvalidCommand),
+            2 => Some(IapError::SrcAddrError),
+            3 => Some(IapError::DstAddrError),
+            4 => Some(IapError::SrcAddrNotMapped),
+            5 => Some(IapError::DstAddrNotMapped),
+            6 => Some(IapError::CountError),
+            7 => Some(IapError::InvalidSector),
+            8 => Some(IapError::SectorNotBlank),
+            9 => Some(IapError::SectorNotPrepared),
+            10 => Some(IapError::CompareError),
+            11 => Some(IapError::Busy),
+            12 => Some(IapError::ParamError),
+            13 => Some(IapError::AddrError),
+            14 => Some(IapError::AddrNotMapped),
+            15 => Some(IapError::CmdLocked),
+            16 => Some(IapError::InvalidCode),
+            17 => Some(IapError::InvalidBaudRate),
+            18 => Some(IapError::InvalidStopBit),
+            19 => Some(IapError::CodeReadProtectionEnabled),
+            other => Some(IapError::Unknown(other)),
+        }
+    }
+}
+
+// The erase and program routines need the system clock frequency in kHz to
+// calculate their timing.
+fn cclk_khz(clocks: &clock::Clocks) -> u32 {
+    clocks.system_clock() / 1000
+}
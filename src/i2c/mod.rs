@@ -1,6 +1,7 @@
 //! Interface to the I2C peripheral.
 
 use crate::pins;
+use crate::wakeup;
 use core::marker::PhantomData;
 
 pub mod mode;
@@ -366,6 +367,23 @@ where
         }
         I2C::new()
     }
+
+    /// Allows the I2C peripheral's interrupt to wake the device from
+    /// deep-sleep or power-down mode.
+    ///
+    /// This is available only in device mode, because the host drives SCL
+    /// and so the peripheral can still recognize its address while the
+    /// system clock is stopped. The peripheral's interrupt must also be
+    /// enabled for the relevant events in order for it to wake the device.
+    pub fn enable_wakeup(&mut self) {
+        wakeup::set_peripheral(wakeup::I2C, true);
+    }
+
+    /// Prevents the I2C peripheral's interrupt from waking the device from
+    /// deep-sleep or power-down mode.
+    pub fn disable_wakeup(&mut self) {
+        wakeup::set_peripheral(wakeup::I2C, false);
+    }
 }
 
 /// ## Monitor mode methods
@@ -39,6 +39,10 @@
 //! // needed, which automatically configures the switch matrix.
 //! let pins = p.pins;
 //!
+//! // Freezing the clock configuration gives the clock rates that peripherals
+//! // need to calculate their own clock dividers.
+//! let clocks = p.clock.freeze();
+//!
 //! // Use GPIO pins 12 and 14 as the SCLK and MOSI signals respectively.
 //! // An SCLK pin is always required to activate an SPI peripheral, but
 //! // the other signals can be assigned selectively depending on the needs
@@ -52,6 +56,7 @@
 //!             sclk_mode: embedded_hal::spi::MODE_0,
 //!             bit_order: hal::spi::cfg::BitOrder::MSBFirst,
 //!         },
+//!         Some((4_000_000, &clocks)),
 //!     )
 //!     .with_mosi(pins.gpio14);
 //!
@@ -76,10 +81,18 @@ pub extern crate lpc81x_pac as lpc81x;
 pub use lpc81x::Interrupt;
 pub use lpc81x::NVIC_PRIO_BITS;
 
+pub mod bod;
+pub mod clock;
+pub mod cmp;
+pub mod crc;
+pub mod flash;
 pub mod i2c;
 pub mod pinint;
 pub mod pins;
+pub mod sct;
 pub mod spi;
+pub mod usart;
+mod wakeup;
 
 /// Singleton container for the peripherals modeled by this HAL crate.
 ///
@@ -107,6 +120,27 @@ pub struct Peripherals {
 
     pub pin_interrupts: pinint::Inactive,
 
+    /// Controls the fixed-pin functions for serial wire debug and external
+    /// reset, which are both enabled at boot.
+    pub fixed_functions: pins::fixed::FixedFunctions<pins::fixed::Enabled, pins::fixed::Enabled>,
+
+    /// The shared clock dividers for the digital input glitch filters.
+    pub glitch_filter: pins::filter::GlitchFilter,
+
+    /// The clock configuration, which must be frozen to obtain the clock
+    /// frequencies needed by some peripherals.
+    pub clock: clock::ClockConfig,
+
+    /// The CLKOUT clock output function, initially not assigned to any pin.
+    pub clkout: clock::clkout::ClkOut<pins::mode::Unassigned>,
+
+    /// The flash memory controller, for erasing and programming flash using
+    /// the IAP routines in the boot ROM.
+    pub flash: flash::Flash,
+
+    /// The brown-out detector.
+    pub bod: bod::BOD,
+
     /// The first SPI peripheral, initially inactive.
     pub spi0: spi::SPI0<
         spi::mode::Inactive,
@@ -128,6 +162,57 @@ pub struct Peripherals {
         pins::mode::Unassigned,
     >,
 
+    /// The analog comparator, initially inactive.
+    pub cmp: cmp::CMP<
+        cmp::mode::Inactive,
+        pins::mode::Unassigned,
+        pins::mode::Unassigned,
+        pins::mode::Unassigned,
+    >,
+
+    /// The CRC engine, initially inactive.
+    pub crc: crc::CRC<crc::mode::Inactive>,
+
+    /// The state configurable timer, which is used by the RC-timing mode of
+    /// the comparator ADC.
+    pub sct: sct::SCT,
+
+    /// The shared USART peripheral clock, which must be frozen before any of
+    /// the USARTs can be activated.
+    pub usart_clock: usart::clock::ClockConfig,
+
+    /// The first USART peripheral, initially inactive.
+    pub usart0: usart::USART0<
+        usart::mode::Inactive,
+        pins::mode::Unassigned,
+        pins::mode::Unassigned,
+        pins::mode::Unassigned,
+        pins::mode::Unassigned,
+        pins::mode::Unassigned,
+    >,
+
+    /// The second USART peripheral, initially inactive.
+    pub usart1: usart::USART1<
+        usart::mode::Inactive,
+        pins::mode::Unassigned,
+        pins::mode::Unassigned,
+        pins::mode::Unassigned,
+        pins::mode::Unassigned,
+        pins::mode::Unassigned,
+    >,
+
+    /// The third USART peripheral, initially inactive.
+    ///
+    /// This device is only present in the LPC812 models.
+    pub usart2: usart::USART2<
+        usart::mode::Inactive,
+        pins::mode::Unassigned,
+        pins::mode::Unassigned,
+        pins::mode::Unassigned,
+        pins::mode::Unassigned,
+        pins::mode::Unassigned,
+    >,
+
     // The I2C peripheral, initially inactive.
     pub i2c: i2c::I2C<
         pins::mode::Unassigned,
@@ -144,9 +229,22 @@ impl Peripherals {
             pins: pins::Pins::new(),
             pin_inputs: pins::PinInputs::new(),
             pin_interrupts: pinint::Inactive::new(),
+            fixed_functions: pins::fixed::FixedFunctions::new(),
+            glitch_filter: pins::filter::GlitchFilter::new(),
+            clock: clock::ClockConfig::new(),
+            clkout: clock::clkout::ClkOut::new(),
+            flash: flash::Flash::new(),
+            bod: bod::BOD::new(),
             spi0: spi::SPI0::new(),
             spi1: spi::SPI1::new(),
+            cmp: cmp::CMP::new(),
+            crc: crc::CRC::new(),
+            sct: sct::SCT::new(),
             i2c: i2c::I2C::new(),
+            usart_clock: usart::clock::ClockConfig::new(),
+            usart0: usart::USART0::new(),
+            usart1: usart::USART1::new(),
+            usart2: usart::USART2::new(),
         }
     }
 
@@ -176,17 +274,74 @@ impl Peripherals {
 
     /// Returns a value representing the specific LPC8xx model of the current
     /// device, allowing dynamic inspection of some details that vary by model.
-    pub fn model(&self) -> Model {
+    ///
+    /// Returns `None` if the device ID is not one of the LPC81x models known
+    /// to this library. The raw device ID is available from `part_id`.
+    pub fn model(&self) -> Option<Model> {
+        Model::current()
+    }
+
+    /// Reads the part identification number using the IAP routines in the
+    /// boot ROM.
+    ///
+    /// This is the same value that `model` decodes, so it can be used to
+    /// identify LPC8xx variants that `model` does not recognize.
+    pub fn part_id(&self) -> Result<u32, flash::IapError> {
+        let result = flash::iap::call([flash::iap::READ_PART_ID, 0, 0, 0, 0])?;
+        Ok(result[0])
+    }
+
+    /// Reads the version number of the boot code using the IAP routines in
+    /// the boot ROM, returning the major and minor version numbers.
+    pub fn boot_code_version(&self) -> Result<(u8, u8), flash::IapError> {
+        let result = flash::iap::call([flash::iap::READ_BOOT_CODE_VERSION, 0, 0, 0, 0])?;
+        Ok(((result[0] >> 8) as u8, result[0] as u8))
+    }
+
+    /// Reads the 128-bit serial number that is unique to each device, using
+    /// the IAP routines in the boot ROM.
+    ///
+    /// The result is four 32-bit words, in the order that the boot ROM
+    /// returns them.
+    pub fn unique_id(&self) -> Result<[u32; 4], flash::IapError> {
+        flash::iap::call([flash::iap::READ_UID, 0, 0, 0, 0])
+    }
+
+    /// Returns the sources of the resets that have occurred since the reset
+    /// status was last cleared.
+    ///
+    /// The reset status is not cleared automatically by later resets, so
+    /// more than one source may be reported. Call
+    /// `clear_reset_cause` once the status has been checked so that the next
+    /// reset will be reported alone.
+    pub fn reset_cause(&self) -> ResetCause {
         let syscon = lpc81x_pac::SYSCON::ptr();
-        let raw = unsafe { (*syscon).device_id.read().deviceid().bits() };
-        match raw {
-            0x00008100 => Model::LPC810M021FN8,
-            0x00008110 => Model::LPC811M001JDH16,
-            0x00008120 => Model::LPC812M101JDH16,
-            0x00008121 => Model::LPC812M101JD20,
-            0x00008122 => Model::LPC812M101JDH20,
-            0x00008123 => Model::LPC812M101JTB16,
-            _ => panic!("unsupported model"),
+        let r = unsafe { (*syscon).sysrststat.read() };
+        ResetCause {
+            power_on: r.por().bit_is_set(),
+            external: r.extrst().bit_is_set(),
+            watchdog: r.wdt().bit_is_set(),
+            brown_out: r.bod().bit_is_set(),
+            system: r.sysrst().bit_is_set(),
+        }
+    }
+
+    /// Clears the reset status reported by `reset_cause`.
+    pub fn clear_reset_cause(&mut self) {
+        let syscon = lpc81x_pac::SYSCON::ptr();
+        unsafe {
+            (*syscon).sysrststat.write(|w| {
+                w.por()
+                    .set_bit()
+                    .extrst()
+                    .set_bit()
+                    .wdt()
+                    .set_bit()
+                    .bod()
+                    .set_bit()
+                    .sysrst()
+                    .set_bit()
+            })
         }
     }
 
@@ -216,7 +371,51 @@ pub enum Model {
     LPC812M101JTB16,
 }
 
+/// Describes the sources of the resets reported by
+/// `Peripherals::reset_cause`.
+#[derive(Clone, Copy, Debug)]
+pub struct ResetCause {
+    /// A power-on reset occurred.
+    pub power_on: bool,
+
+    /// The external reset pin was asserted.
+    pub external: bool,
+
+    /// The watchdog timer reset the device.
+    pub watchdog: bool,
+
+    /// The brown-out detector reset the device.
+    pub brown_out: bool,
+
+    /// Software requested a reset through the system reset request in the
+    /// Cortex-M0+ application interrupt and reset control register.
+    pub system: bool,
+}
+
 impl Model {
+    pub(crate) fn current() -> Option<Self> {
+        let syscon = lpc81x_pac::SYSCON::ptr();
+        let raw = unsafe { (*syscon).device_id.read().deviceid().bits() };
+        match raw {
+            0x00008100 => Some(Model::LPC810M021FN8),
+            0x00008110 => Some(Model::LPC811M001JDH16),
+            0x00008120 => Some(Model::LPC812M101JDH16),
+            0x00008121 => Some(Model::LPC812M101JD20),
+            0x00008122 => Some(Model::LPC812M101JDH20),
+            0x00008123 => Some(Model::LPC812M101JTB16),
+            _ => None,
+        }
+    }
+
+    /// Returns the size of the flash memory for this model, in bytes.
+    pub fn flash_size(&self) -> usize {
+        match self {
+            Model::LPC810M021FN8 => 4 * 1024,
+            Model::LPC811M001JDH16 => 8 * 1024,
+            _ => 16 * 1024,
+        }
+    }
+
     /// Returns true if the given pin is available for this model.
     ///
     /// This library does not prevent using pins that are not available on
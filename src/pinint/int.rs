@@ -1,5 +1,6 @@
 use super::mode;
 use crate::pins;
+use crate::wakeup;
 use core::marker::PhantomData;
 
 macro_rules! pinint {
@@ -95,6 +96,23 @@ macro_rules! pinint {
                 }
             }
 
+            /// Allow this interrupt to wake the device from deep-sleep or
+            /// power-down mode.
+            ///
+            /// The interrupt must also be enabled using `enable` in order
+            /// for it to wake the device.
+            #[inline(always)]
+            pub fn enable_wakeup(&self) {
+                wakeup::set_pin_interrupt($idx, true);
+            }
+
+            /// Prevent this interrupt from waking the device from deep-sleep
+            /// or power-down mode.
+            #[inline(always)]
+            pub fn disable_wakeup(&self) {
+                wakeup::set_pin_interrupt($idx, false);
+            }
+
             /// Clear any active rising or falling edge notifications.
             ///
             /// The interrupt service routine must call this before returning
@@ -116,6 +134,7 @@ macro_rules! pinint {
                 let syscon = lpc81x_pac::SYSCON::ptr();
                 let periph = lpc81x_pac::PIN_INT::ptr();
                 self.disable();
+                self.disable_wakeup();
                 unsafe {
                     (*syscon).pintsel[$idx].write(|w| {
                         // The reset value is zero, but that's also how we'd
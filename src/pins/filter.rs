@@ -0,0 +1,151 @@
+//! Programmable digital input glitch filters.
+//!
+//! Each pin's digital input can optionally pass through a filter that
+//! rejects pulses shorter than one, two, or three periods of a filter clock.
+//! The filter clocks are derived from the main clock by seven shared
+//! dividers, which are owned by the `GlitchFilter` object in `Peripherals`.
+//!
+//! To filter a pin, first configure one of the dividers and then attach the
+//! pin to it:
+//!
+//! ```rust
+//! let clocks = p.clock.freeze();
+//! let mut filter = p.glitch_filter;
+//! // Reject pulses shorter than 50µs, sampling three times.
+//! let actual = filter.div0.configure(&clocks, 50_000, hal::pins::filter::Samples::Three);
+//! assert!(actual >= 50_000);
+//!
+//! let mut button = pins.gpio4.to_pull_up_input();
+//! button.set_glitch_filter(&filter.div0, hal::pins::filter::Samples::Three);
+//! ```
+//!
+//! The dividers can divide the main clock by at most 255, so the longest
+//! pulse that can be rejected is 765 main clock periods, using three
+//! samples. With the main clock at the 12MHz reset frequency, that is about
+//! 64µs. Longer glitches, such as switch bounce, must be filtered in
+//! software.
+//!
+//! The filter applies to all digital input functions on the pin, including
+//! GPIO reads and pin interrupts.
+
+use crate::clock;
+use core::marker::PhantomData;
+
+/// Owns the seven filter clock dividers.
+pub struct GlitchFilter {
+    pub div0: Divider0,
+    pub div1: Divider1,
+    pub div2: Divider2,
+    pub div3: Divider3,
+    pub div4: Divider4,
+    pub div5: Divider5,
+    pub div6: Divider6,
+}
+
+impl GlitchFilter {
+    pub(crate) fn new() -> Self {
+        Self {
+            div0: Divider0(PhantomData),
+            div1: Divider1(PhantomData),
+            div2: Divider2(PhantomData),
+            div3: Divider3(PhantomData),
+            div4: Divider4(PhantomData),
+            div5: Divider5(PhantomData),
+            div6: Divider6(PhantomData),
+        }
+    }
+}
+
+/// Selects how many consecutive filter clock periods an input must be
+/// stable for before a change is passed through the filter.
+#[derive(Clone, Copy)]
+pub enum Samples {
+    One,
+    Two,
+    Three,
+}
+
+impl Samples {
+    pub(crate) fn count(self) -> u32 {
+        match self {
+            Samples::One => 1,
+            Samples::Two => 2,
+            Samples::Three => 3,
+        }
+    }
+}
+
+// Trait implemented by types representing the filter clock dividers.
+//
+// Only types in the `lpx81x-hal` crate may implement this trait.
+pub unsafe trait Divider {
+    // INDEX is the value of the CLK_DIV field in a pin's IOCON register that
+    // selects this divider.
+    const INDEX: u8;
+}
+
+macro_rules! divider {
+    ($name:ident, $idx:expr, $reg:ident) => {
+        /// One of the glitch filter clock dividers.
+        pub struct $name(PhantomData<()>);
+
+        impl !Sync for $name {}
+
+        unsafe impl Divider for $name {
+            const INDEX: u8 = $idx;
+        }
+
+        impl $name {
+            /// Sets the divider so that the filter clock is the main clock
+            /// divided by `div`.
+            ///
+            /// A value of zero disables the filter clock, in which case any
+            /// pins attached to this divider will never see their inputs
+            /// change.
+            pub fn set_divider(&mut self, div: u8) {
+                let syscon = lpc81x_pac::SYSCON::ptr();
+                unsafe { (*syscon).$reg.write(|w| w.div().bits(div)) }
+            }
+
+            /// Sets the divider so that pins attached with the given number
+            /// of samples will reject pulses shorter than `min_pulse_ns`
+            /// nanoseconds.
+            ///
+            /// Returns the actual minimum pulse width in nanoseconds, which
+            /// is rounded up to a whole number of filter clock periods and
+            /// limited by the maximum divider of 255. The result is shorter
+            /// than requested if the request exceeds that limit, which is
+            /// 255 main clock periods per sample, so callers that need a
+            /// particular minimum should check it.
+            pub fn configure(
+                &mut self,
+                clocks: &clock::Clocks,
+                min_pulse_ns: u32,
+                samples: Samples,
+            ) -> u32 {
+                let main = clocks.main_clock() as u64;
+                let count = samples.count() as u64;
+
+                let ideal = ((min_pulse_ns as u64) * main + (count * 1_000_000_000 - 1))
+                    / (count * 1_000_000_000);
+                let div = if ideal < 1 {
+                    1
+                } else if ideal > 255 {
+                    255
+                } else {
+                    ideal
+                };
+                self.set_divider(div as u8);
+                ((count * div * 1_000_000_000) / main) as u32
+            }
+        }
+    };
+}
+
+divider!(Divider0, 0, ioconclkdiv0);
+divider!(Divider1, 1, ioconclkdiv1);
+divider!(Divider2, 2, ioconclkdiv2);
+divider!(Divider3, 3, ioconclkdiv3);
+divider!(Divider4, 4, ioconclkdiv4);
+divider!(Divider5, 5, ioconclkdiv5);
+divider!(Divider6, 6, ioconclkdiv6);
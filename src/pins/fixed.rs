@@ -0,0 +1,115 @@
+//! Control of the fixed-pin functions that are active at boot.
+//!
+//! At reset, the serial wire debug (SWD) signals SWCLK and SWDIO are
+//! connected to GPIO pins 3 and 2 respectively, and the external reset
+//! signal is connected to GPIO pin 5. The `FixedFunctions` object in
+//! `Peripherals` can disconnect these to make the pins fully available for
+//! other functions, and reconnect them later.
+
+use super::{mode, pin};
+use core::marker::PhantomData;
+
+/// Controls the fixed-pin functions SWCLK, SWDIO, and RESET.
+///
+/// The type parameters track whether the SWD and reset functions are
+/// currently enabled. While `SWD` is `Disabled`, GPIO pins 2 and 3 are free
+/// of fixed functions. While `RESET` is `Disabled`, GPIO pin 5 is free of
+/// fixed functions.
+pub struct FixedFunctions<SWD: State, RESET: State>(PhantomData<(SWD, RESET)>);
+
+impl<SWD: State, RESET: State> !Sync for FixedFunctions<SWD, RESET> {}
+
+impl<SWD: State, RESET: State> FixedFunctions<SWD, RESET> {
+    pub(crate) fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<RESET: State> FixedFunctions<Enabled, RESET> {
+    /// Disconnects the serial wire debug signals SWCLK and SWDIO, and
+    /// returns the movable pin object for GPIO pin 3, which is otherwise
+    /// unavailable while SWCLK is active.
+    ///
+    /// Once SWD is disabled, a debugger can no longer connect to the device
+    /// until SWD is enabled again or until the device is reset. To recover a
+    /// device whose program disables SWD early in startup, enter in-system
+    /// programming (ISP) mode at reset.
+    pub fn disable_swd(self) -> (FixedFunctions<Disabled, RESET>, pin::Pin3<mode::Unassigned>) {
+        let swm = lpc81x_pac::SWM::ptr();
+        unsafe {
+            (*swm).pinenable0.modify(|_, w| {
+                w.swclk_en()
+                    .disable_swclk_gpio_()
+                    .swdio_en()
+                    .disable_swdio_gpio_()
+            })
+        }
+        (FixedFunctions::new(), pin::Pin3::new())
+    }
+}
+
+impl<RESET: State> FixedFunctions<Disabled, RESET> {
+    /// Reconnects the serial wire debug signals SWCLK and SWDIO, consuming
+    /// the movable pin object for GPIO pin 3.
+    ///
+    /// The caller must ensure that no other function is still assigned to
+    /// GPIO pin 2, because it would conflict with the SWDIO signal.
+    pub fn enable_swd(self, pin: pin::Pin3<mode::Unassigned>) -> FixedFunctions<Enabled, RESET> {
+        let swm = lpc81x_pac::SWM::ptr();
+        unsafe {
+            (*swm).pinenable0.modify(|_, w| {
+                w.swclk_en()
+                    .enable_swclk_this_f()
+                    .swdio_en()
+                    .enable_swdio_this_f()
+            })
+        }
+        unused(pin);
+        FixedFunctions::new()
+    }
+}
+
+impl<SWD: State> FixedFunctions<SWD, Enabled> {
+    /// Disconnects the external reset signal from GPIO pin 5, so that
+    /// driving that pin low no longer resets the device.
+    pub fn disable_reset(self) -> FixedFunctions<SWD, Disabled> {
+        let swm = lpc81x_pac::SWM::ptr();
+        unsafe {
+            (*swm)
+                .pinenable0
+                .modify(|_, w| w.reset_en().disable_reset_gpio_())
+        }
+        FixedFunctions::new()
+    }
+}
+
+impl<SWD: State> FixedFunctions<SWD, Disabled> {
+    /// Reconnects the external reset signal to GPIO pin 5.
+    ///
+    /// As at boot, other functions may remain assigned to GPIO pin 5 while
+    /// the reset function is enabled, but the device will reset whenever
+    /// the pin is driven low.
+    pub fn enable_reset(self) -> FixedFunctions<SWD, Enabled> {
+        let swm = lpc81x_pac::SWM::ptr();
+        unsafe {
+            (*swm)
+                .pinenable0
+                .modify(|_, w| w.reset_en().enable_reset_this_f())
+        }
+        FixedFunctions::new()
+    }
+}
+
+pub enum Enabled {}
+pub enum Disabled {}
+
+// Trait implemented by types representing whether a fixed-pin function is
+// enabled.
+//
+// Only types in the `lpx81x-hal` crate may implement this trait.
+pub unsafe trait State {}
+unsafe impl State for Enabled {}
+unsafe impl State for Disabled {}
+
+#[inline(always)]
+fn unused<T>(_v: T) {}
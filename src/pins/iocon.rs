@@ -0,0 +1,46 @@
+// Helpers for the I/O configuration (IOCON) registers.
+//
+// Each pin has its own IOCON register, and the PAC models each one as a
+// separate type, so the pin types write them via their `modify_iocon` helper
+// using raw bit values built from the constants here.
+
+// MODE field: selects the on-chip pull-up/pull-down resistors. Not present
+// for the true open-drain pins 10 and 11.
+pub(crate) const MODE_MASK: u32 = 0b11 << 3;
+pub(crate) const MODE_INACTIVE: u32 = 0b00 << 3;
+pub(crate) const MODE_PULL_DOWN: u32 = 0b01 << 3;
+pub(crate) const MODE_PULL_UP: u32 = 0b10 << 3;
+pub(crate) const MODE_REPEATER: u32 = 0b11 << 3;
+
+// HYS field: enables input hysteresis. Not present for pins 10 and 11.
+pub(crate) const HYS: u32 = 1 << 5;
+
+// INV field: inverts the input seen by all digital input functions.
+pub(crate) const INV: u32 = 1 << 6;
+
+// OD field: enables pseudo open-drain mode. Not present for pins 10 and 11,
+// which are always true open-drain.
+pub(crate) const OD: u32 = 1 << 10;
+
+// I2CMODE field: selects the behavior of the true open-drain I2C pads on
+// pins 10 and 11. Only present for those pins.
+pub(crate) const I2CMODE_MASK: u32 = 0b11 << 8;
+pub(crate) const I2CMODE_GPIO: u32 = 0b01 << 8;
+
+// S_MODE field: selects how many filter clock periods the digital input
+// must be stable for, or zero to bypass the filter.
+pub(crate) const S_MODE_MASK: u32 = 0b11 << 11;
+pub(crate) const S_MODE_SHIFT: u32 = 11;
+
+// CLK_DIV field: selects which IOCONCLKDIV divider drives the filter.
+pub(crate) const CLK_DIV_MASK: u32 = 0b111 << 13;
+pub(crate) const CLK_DIV_SHIFT: u32 = 13;
+
+#[inline(always)]
+pub(crate) fn enable_clock() {
+    let syscon = lpc81x_pac::SYSCON::ptr();
+    unsafe {
+        (*syscon).sysahbclkctrl.modify(|_, w| w.iocon().enable());
+    }
+    cortex_m::asm::dsb();
+}
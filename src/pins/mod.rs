@@ -1,5 +1,8 @@
 //! I/O pin handling.
 
+pub mod filter;
+pub mod fixed;
+pub(crate) mod iocon;
 pub mod mode;
 pub mod pin;
 
@@ -35,6 +38,16 @@ pub unsafe trait InputPin: Pin {}
 // Only types in the `lpx81x-hal` crate may implement this trait.
 pub unsafe trait PinMode {}
 
+// Trait implemented by types representing the configuration of a pin's
+// on-chip pull-up and pull-down resistors.
+//
+// Only types in the `lpx81x-hal` crate may implement this trait.
+pub unsafe trait Pull {
+    // MODE is the value of the MODE field in the pin's IOCON register,
+    // already shifted into position.
+    const MODE: u32;
+}
+
 // Trait implemented by types representing pin assignments.
 //
 // Only types in the `lpx81x-hal` crate may implement this trait.
@@ -46,9 +59,9 @@ pub unsafe trait PinAssignment {}
 /// switch matrix, routing each pin to an appropriate internal peripheral.
 ///
 /// Note that GPIO pin 3 is not included because at system boot it has been
-/// assigned to the serial wire debug (SWD) function SWDIO. To assign another
-/// function to that pin, you must deactivate the SWD peripheral (via the
-/// main `Parts` object) to obtain the movable pin object for gpio3.
+/// assigned to the serial wire debug (SWD) function SWCLK. To assign another
+/// function to that pin, you must deactivate the SWD interface (via
+/// `Peripherals::fixed_functions`) to obtain the movable pin object for gpio3.
 pub struct Pins {
     /// GPIO pin 0. At boot, this pin is in a high-impedance state with no
     /// attached input devices.
@@ -66,7 +79,8 @@ pub struct Pins {
     ///
     /// The fixed-pin functions ACMP_I2 and CLKIN can be activated on this pin
     /// only. Activating ACMP_I2 will cause all digital input functions on this
-    /// pin to read consistently low.
+    /// pin to read consistently low. CLKIN can be activated by passing this
+    /// pin to `clock::source::ClkIn::new`.
     ///
     /// On an LPC810 (DIP8 package) this pin is sampled on startup by the
     /// bootloader to conditionally enter in-system programming (ISP) mode.
@@ -76,15 +90,16 @@ pub struct Pins {
     pub gpio1: pin::Pin1<mode::Unassigned>,
 
     /// GPIO pin 2. At boot, this pin is in a high-impedance state but is
-    /// connected to the SWCLK signal of the serial wire debug interface.
+    /// connected to the SWDIO signal of the serial wire debug interface.
     ///
-    /// The SWCLK signal is a fixed-pin function that can be activated on this
+    /// The SWDIO signal is a fixed-pin function that can be activated on this
     /// pin only.
     ///
-    /// Assigning other functions to this pin with the SWCLK function connected
+    /// Assigning other functions to this pin with the SWDIO function connected
     /// is valid, but the operation of those other functions may conflict with
     /// the operation of the SWD interface. To avoid such conflicts, deactivate
-    /// the debug interface.
+    /// the debug interface via `Peripherals::fixed_functions`, after which
+    /// this pin is free of fixed functions.
     pub gpio2: pin::Pin2<mode::Unassigned>,
 
     /// GPIO pin 4. At boot, this pin is in a high-impedance state with no
@@ -97,7 +112,10 @@ pub struct Pins {
     /// GPIO pin 5. At boot, this pin is in a high-impedance state with no
     /// attached input devices.
     ///
-    /// The external reset signal can be activated on this pin only.
+    /// The external reset signal can be activated on this pin only, and is
+    /// active at boot, so driving this pin low resets the device. It can be
+    /// deactivated via `Peripherals::fixed_functions`, after which this pin
+    /// is free of fixed functions.
     pub gpio5: pin::Pin5<mode::Unassigned>,
 
     /// GPIO pin 6. At boot, this pin is in a high-impedance state with no
@@ -115,13 +133,16 @@ pub struct Pins {
     /// GPIO pin 8. At boot, this pin is in a high-impedance state with no
     /// attached input devices.
     ///
-    /// The fixed-pin function XTALIN can be activated on this pin only.
+    /// The fixed-pin function XTALIN can be activated on this pin only, by
+    /// passing it to `clock::source::SystemOscillator::crystal` or
+    /// `clock::source::SystemOscillator::bypass`.
     pub gpio8: pin::Pin8<mode::Unassigned>,
 
     /// GPIO pin 9. At boot, this pin is in a high-impedance state with no
     /// attached input devices.
     ///
-    /// The fixed-pin function XTALOUT can be activated on this pin only.
+    /// The fixed-pin function XTALOUT can be activated on this pin only, by
+    /// passing it to `clock::source::SystemOscillator::crystal`.
     pub gpio9: pin::Pin9<mode::Unassigned>,
 
     /// GPIO pin 10. At boot, this pin is in a high-impedance state with no
@@ -129,9 +150,10 @@ pub struct Pins {
     ///
     /// Although I2C0_SCK can be assigned to any pin, it is open-drain only
     /// when assigned to GPIO pin 10. It can be configured as true open-drain
-    /// even if the I2C function is not assigned.
+    /// even if the I2C function is not assigned, using `to_open_drain_output`.
     ///
-    /// This pin does not have a programmable pull-up resistor.
+    /// This pin does not have programmable pull-up or pull-down resistors,
+    /// so it has no methods for selecting a pull-resistor input mode.
     pub gpio10: pin::Pin10<mode::Unassigned>,
 
     /// GPIO pin 11. At boot, this pin is in a high-impedance state with no
@@ -139,9 +161,10 @@ pub struct Pins {
     ///
     /// Although I2C0_SDA can be assigned to any pin, it is open-drain only
     /// when assigned to GPIO pin 11. It can be configured as true open-drain
-    /// even if the I2C function is not assigned.
+    /// even if the I2C function is not assigned, using `to_open_drain_output`.
     ///
-    /// This pin does not have a programmable pull-up resistor.
+    /// This pin does not have programmable pull-up or pull-down resistors,
+    /// so it has no methods for selecting a pull-resistor input mode.
     pub gpio11: pin::Pin11<mode::Unassigned>,
 
     /// GPIO pin 12. At boot, this pin is in a high-impedance state with no
@@ -238,7 +261,8 @@ pub struct PinInputs {
     /// which case driving the pin low will cause a system reset regardless
     /// of what other functions might simultaneously be assigned to the pin.
     /// This reset behavior is activated by default at boot, but can be
-    /// disabled by deactivating the external reset via the `Parts` struct.
+    /// disabled by deactivating the external reset via
+    /// `Peripherals::fixed_functions`.
     pub gpio5: pin::Pin5<mode::DigitalInput>,
 
     /// GPIO pin 6.
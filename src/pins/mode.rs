@@ -1,3 +1,5 @@
+use core::marker::PhantomData;
+
 macro_rules! mode {
     ($name:ident) => {
         pub enum $name {}
@@ -8,11 +10,32 @@ macro_rules! mode {
 
 mode!(Unassigned);
 mode!(DigitalOutput);
+mode!(OpenDrainOutput);
 mode!(DigitalInput);
 mode!(SWM);
 
 unsafe impl super::PinAssignment for Unassigned {}
 
+// A marker type used to represent a pin that is used only as a digital
+// input, with its on-chip pull resistors configured as described by PULL.
+pub struct Input<PULL: super::Pull>(PhantomData<PULL>, void::Void);
+unsafe impl<PULL: super::Pull> super::PinMode for Input<PULL> {}
+
+macro_rules! pull {
+    ($name:ident, $mode:expr) => {
+        pub enum $name {}
+
+        unsafe impl super::Pull for $name {
+            const MODE: u32 = $mode;
+        }
+    };
+}
+
+pull!(Floating, super::iocon::MODE_INACTIVE);
+pull!(PullDown, super::iocon::MODE_PULL_DOWN);
+pull!(PullUp, super::iocon::MODE_PULL_UP);
+pull!(Repeater, super::iocon::MODE_REPEATER);
+
 // A marker type used to represent that a function is assigned to a pin.
 pub struct Assigned<PIN: super::Pin>(PIN, void::Void);
 unsafe impl<T: super::Pin> super::PinAssignment for Assigned<T> {}
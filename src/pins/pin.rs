@@ -1,8 +1,39 @@
-use super::{mode, Pin, PinMode};
+use super::{filter, iocon, mode, Pin, PinMode};
 use core::marker::PhantomData;
 
 macro_rules! pin {
-    ($name:ident, $assign_v:expr) => {
+    ($name:ident, $assign_v:expr, $iocon:ident) => {
+        pin!(@impl $name, $assign_v, $iocon, iocon::OD, iocon::OD);
+    };
+    ($name:ident, $assign_v:expr, $iocon:ident, true_open_drain) => {
+        pin!(@impl $name, $assign_v, $iocon, iocon::I2CMODE_MASK, iocon::I2CMODE_GPIO);
+    };
+    (@glitch_filter) => {
+        /// Attaches the pin's digital input to the given glitch filter
+        /// clock divider, so that input changes are ignored until they
+        /// have been stable for the given number of filter clock periods.
+        ///
+        /// The filter affects all digital input functions on the pin. See
+        /// the `filter` module for more information.
+        pub fn set_glitch_filter<D: filter::Divider>(
+            &mut self,
+            _divider: &D,
+            samples: filter::Samples,
+        ) {
+            Self::modify_iocon(|r| {
+                (r & !(iocon::S_MODE_MASK | iocon::CLK_DIV_MASK))
+                    | (samples.count() << iocon::S_MODE_SHIFT)
+                    | ((D::INDEX as u32) << iocon::CLK_DIV_SHIFT)
+            });
+        }
+
+        /// Detaches the pin's digital input from any glitch filter, which
+        /// is the reset configuration.
+        pub fn bypass_glitch_filter(&mut self) {
+            Self::modify_iocon(|r| r & !(iocon::S_MODE_MASK | iocon::CLK_DIV_MASK));
+        }
+    };
+    (@impl $name:ident, $assign_v:expr, $iocon:ident, $od_mask:expr, $od_bits:expr) => {
         pub struct $name<MODE: PinMode>(PhantomData<MODE>);
 
         impl<MODE: super::PinMode> $name<MODE> {
@@ -21,11 +52,34 @@ macro_rules! pin {
             pub fn digital_input(&self) -> $name<mode::DigitalInput> {
                 $name::<mode::DigitalInput>(PhantomData)
             }
+
+            // Updates the pin's IOCON register by passing its current raw
+            // value to the given function and writing back the result.
+            #[inline(always)]
+            fn modify_iocon<F: FnOnce(u32) -> u32>(f: F) {
+                let iocon = lpc81x_pac::IOCON::ptr();
+                iocon::enable_clock();
+                unsafe { (*iocon).$iocon.modify(|r, w| w.bits(f(r.bits()))) }
+            }
+
+            // Puts the pin's GPIO function back in input mode, which is its
+            // reset state, so that it no longer drives the pin.
+            #[inline(always)]
+            fn clear_dir() {
+                let gpio = lpc81x_pac::GPIO_PORT::ptr();
+                unsafe {
+                    (*gpio)
+                        .dir0
+                        .modify(|r, w| w.bits(r.bits() & !Self::REG_MASK));
+                }
+            }
         }
 
         impl<MODE: super::PinMode> !Sync for $name<MODE> {}
 
         impl $name<mode::Unassigned> {
+            pin!(@glitch_filter);
+
             /// Configure the pin's output portion for general-purpose digital
             /// output.
             ///
@@ -57,6 +111,68 @@ macro_rules! pin {
 
                 $name(PhantomData)
             }
+
+            /// Configure the pin's output portion for open-drain digital
+            /// output.
+            ///
+            /// In open-drain mode the pin actively drives the line only when
+            /// low. When set high, the pin releases the line and an external
+            /// (or on-chip) pull-up resistor must pull it high. This allows
+            /// several devices to share a line, such as for 1-Wire buses or
+            /// shared interrupt signals.
+            ///
+            /// The result of this method implements both the embedded-hal
+            /// digital v2 `OutputPin` and `InputPin` traits, so the owner can
+            /// release the line and then read back its actual state.
+            ///
+            /// GPIO pins 10 and 11 are always open-drain, so for those this
+            /// method configures the I2C pad for standard GPIO behavior.
+            /// Those pins have no on-chip pull-up resistor.
+            pub fn to_open_drain_output(self, high: bool) -> $name<mode::OpenDrainOutput> {
+                // Enable open-drain mode first, so that we won't briefly drive
+                // the line high when we activate the output.
+                Self::modify_iocon(|r| (r & !$od_mask) | $od_bits);
+                self.to_digital_output(high);
+                $name(PhantomData)
+            }
+        }
+
+        impl $name<mode::OpenDrainOutput> {
+            /// Consumes the open-drain output pin and returns it unassigned,
+            /// with the pin no longer driven and its open-drain setting
+            /// returned to its reset value.
+            ///
+            /// For GPIO pins 10 and 11, this returns the I2C pad to standard
+            /// I2C mode.
+            pub fn to_unassigned(self) -> $name<mode::Unassigned> {
+                // Stop driving the line first, so that we won't briefly drive
+                // it high when we leave open-drain mode.
+                Self::clear_dir();
+                Self::modify_iocon(|r| r & !$od_mask);
+                $name(PhantomData)
+            }
+        }
+
+        // The glitch filter is configured only through pins that are owned,
+        // rather than through the copyable input portion, so that only one
+        // owner can change it.
+        impl<PULL: super::Pull> $name<mode::Input<PULL>> {
+            pin!(@glitch_filter);
+
+            /// Enables or disables inversion of the input signal.
+            ///
+            /// When inversion is enabled, all digital input functions on this
+            /// pin see the inverse of the level on the pin, including the
+            /// `InputPin` implementation and any pin interrupts.
+            pub fn set_inverted(&mut self, inverted: bool) {
+                Self::modify_iocon(|r| {
+                    if inverted {
+                        r | iocon::INV
+                    } else {
+                        r & !iocon::INV
+                    }
+                });
+            }
         }
 
         unsafe impl<MODE: super::PinMode> Pin for $name<MODE> {
@@ -66,6 +182,8 @@ macro_rules! pin {
         unsafe impl super::UnassignedPin for $name<mode::Unassigned> {}
         unsafe impl super::InputPin for $name<mode::DigitalInput> {}
         unsafe impl super::InputPin for $name<mode::Unassigned> {}
+        unsafe impl<PULL: super::Pull> super::InputPin for $name<mode::Input<PULL>> {}
+        unsafe impl super::InputPin for $name<mode::OpenDrainOutput> {}
 
         impl embedded_hal::digital::v2::InputPin for $name<mode::DigitalInput> {
             type Error = !;
@@ -81,6 +199,20 @@ macro_rules! pin {
             }
         }
 
+        impl<PULL: super::Pull> embedded_hal::digital::v2::InputPin for $name<mode::Input<PULL>> {
+            type Error = !;
+
+            fn is_high(&self) -> Result<bool, !> {
+                let gpio = lpc81x_pac::GPIO_PORT::ptr();
+                Ok(unsafe { (*gpio).b[Self::NUMBER as usize].read().bits() != 0 })
+            }
+
+            fn is_low(&self) -> Result<bool, !> {
+                let gpio = lpc81x_pac::GPIO_PORT::ptr();
+                Ok(unsafe { (*gpio).b[Self::NUMBER as usize].read().bits() == 0 })
+            }
+        }
+
         impl embedded_hal::digital::v2::OutputPin for $name<mode::DigitalOutput> {
             type Error = !;
 
@@ -107,6 +239,48 @@ macro_rules! pin {
             }
         }
 
+        impl embedded_hal::digital::v2::OutputPin for $name<mode::OpenDrainOutput> {
+            type Error = !;
+
+            fn set_high(&mut self) -> Result<(), !> {
+                let gpio = lpc81x_pac::GPIO_PORT::ptr();
+                unsafe { (*gpio).set0.write(|w| w.bits(Self::REG_MASK)) };
+                Ok(())
+            }
+
+            fn set_low(&mut self) -> Result<(), !> {
+                let gpio = lpc81x_pac::GPIO_PORT::ptr();
+                unsafe { (*gpio).clr0.write(|w| w.bits(Self::REG_MASK)) };
+                Ok(())
+            }
+        }
+
+        impl embedded_hal::digital::v2::ToggleableOutputPin for $name<mode::OpenDrainOutput> {
+            type Error = !;
+
+            fn toggle(&mut self) -> Result<(), !> {
+                let gpio = lpc81x_pac::GPIO_PORT::ptr();
+                unsafe { (*gpio).not0.write(|w| w.bits(Self::REG_MASK)) };
+                Ok(())
+            }
+        }
+
+        /// Reads the actual level of the line, which may be low even while
+        /// the output is set high if another device is driving it low.
+        impl embedded_hal::digital::v2::InputPin for $name<mode::OpenDrainOutput> {
+            type Error = !;
+
+            fn is_high(&self) -> Result<bool, !> {
+                let gpio = lpc81x_pac::GPIO_PORT::ptr();
+                Ok(unsafe { (*gpio).b[Self::NUMBER as usize].read().bits() != 0 })
+            }
+
+            fn is_low(&self) -> Result<bool, !> {
+                let gpio = lpc81x_pac::GPIO_PORT::ptr();
+                Ok(unsafe { (*gpio).b[Self::NUMBER as usize].read().bits() == 0 })
+            }
+        }
+
         /// The input portion of a pin can be freely copied, because multiple
         /// input functions can coexist on the same pin.
         impl core::marker::Copy for $name<mode::DigitalInput> {}
@@ -118,21 +292,144 @@ macro_rules! pin {
     };
 }
 
-pin!(Pin0, 0);
-pin!(Pin1, 1);
-pin!(Pin2, 2);
-pin!(Pin3, 3);
-pin!(Pin4, 4);
-pin!(Pin5, 5);
-pin!(Pin6, 6);
-pin!(Pin7, 7);
-pin!(Pin8, 8);
-pin!(Pin9, 9);
-pin!(Pin10, 10);
-pin!(Pin11, 11);
-pin!(Pin12, 12);
-pin!(Pin13, 13);
-pin!(Pin14, 14);
-pin!(Pin15, 15);
-pin!(Pin16, 16);
-pin!(Pin17, 17);
+// Adds methods for configuring the on-chip pull resistors and hysteresis,
+// which are available on all pins except the true open-drain pins 10 and 11.
+macro_rules! pull_resistors {
+    ($name:ident) => {
+        impl $name<mode::Unassigned> {
+            /// Configure the pin for digital input only, with both of its
+            /// on-chip pull resistors disabled.
+            ///
+            /// The result of this method implements the embedded-hal digital
+            /// v2 `InputPin` trait, and can be used with any function that
+            /// accepts an input pin.
+            pub fn to_floating_input(self) -> $name<mode::Input<mode::Floating>> {
+                Self::to_input()
+            }
+
+            /// Configure the pin for digital input only, with its on-chip
+            /// pull-up resistor enabled.
+            ///
+            /// This is the same resistor configuration the pin has at reset,
+            /// but the result additionally records it in the pin's type.
+            pub fn to_pull_up_input(self) -> $name<mode::Input<mode::PullUp>> {
+                Self::to_input()
+            }
+
+            /// Configure the pin for digital input only, with its on-chip
+            /// pull-down resistor enabled.
+            pub fn to_pull_down_input(self) -> $name<mode::Input<mode::PullDown>> {
+                Self::to_input()
+            }
+
+            /// Configure the pin for digital input only, in repeater mode.
+            ///
+            /// In repeater mode the pull-up resistor is enabled while the pin
+            /// is high and the pull-down resistor is enabled while it is low,
+            /// so the pin retains its last state if the external driver
+            /// becomes high-impedance.
+            pub fn to_repeater_input(self) -> $name<mode::Input<mode::Repeater>> {
+                Self::to_input()
+            }
+
+            #[inline(always)]
+            fn to_input<PULL: super::Pull>() -> $name<mode::Input<PULL>> {
+                Self::clear_dir();
+                Self::modify_iocon(|r| (r & !iocon::MODE_MASK) | PULL::MODE);
+                $name(PhantomData)
+            }
+        }
+
+        impl<PULL: super::Pull> $name<mode::Input<PULL>> {
+            /// Enables or disables input hysteresis, which makes the pin
+            /// less sensitive to noise on slowly-changing input signals.
+            pub fn set_hysteresis(&mut self, enabled: bool) {
+                Self::modify_iocon(|r| {
+                    if enabled {
+                        r | iocon::HYS
+                    } else {
+                        r & !iocon::HYS
+                    }
+                });
+            }
+
+            /// Consumes the input pin and returns it unassigned, with its
+            /// pull resistor, hysteresis and inversion settings returned to
+            /// their reset values.
+            pub fn to_unassigned(self) -> $name<mode::Unassigned> {
+                Self::modify_iocon(|r| {
+                    (r & !(iocon::MODE_MASK | iocon::HYS | iocon::INV)) | iocon::MODE_PULL_UP
+                });
+                $name(PhantomData)
+            }
+        }
+    };
+}
+
+// Adds input-only configuration for the true open-drain pins 10 and 11,
+// which have the INV field but no pull resistors or hysteresis.
+macro_rules! true_open_drain_input {
+    ($name:ident) => {
+        impl $name<mode::Unassigned> {
+            /// Configure the pin for digital input only.
+            ///
+            /// This pin has no on-chip pull resistors, so the input floats
+            /// unless it is driven or pulled externally. The result of this
+            /// method implements the embedded-hal digital v2 `InputPin`
+            /// trait, and can be used with any function that accepts an
+            /// input pin.
+            pub fn to_input(self) -> $name<mode::Input<mode::Floating>> {
+                Self::clear_dir();
+                $name(PhantomData)
+            }
+        }
+
+        impl $name<mode::Input<mode::Floating>> {
+            /// Consumes the input pin and returns it unassigned, with its
+            /// inversion setting returned to its reset value.
+            pub fn to_unassigned(self) -> $name<mode::Unassigned> {
+                Self::modify_iocon(|r| r & !iocon::INV);
+                $name(PhantomData)
+            }
+        }
+    };
+}
+
+pin!(Pin0, 0, pio0_0);
+pin!(Pin1, 1, pio0_1);
+pin!(Pin2, 2, pio0_2);
+pin!(Pin3, 3, pio0_3);
+pin!(Pin4, 4, pio0_4);
+pin!(Pin5, 5, pio0_5);
+pin!(Pin6, 6, pio0_6);
+pin!(Pin7, 7, pio0_7);
+pin!(Pin8, 8, pio0_8);
+pin!(Pin9, 9, pio0_9);
+pin!(Pin10, 10, pio0_10, true_open_drain);
+pin!(Pin11, 11, pio0_11, true_open_drain);
+pin!(Pin12, 12, pio0_12);
+pin!(Pin13, 13, pio0_13);
+pin!(Pin14, 14, pio0_14);
+pin!(Pin15, 15, pio0_15);
+pin!(Pin16, 16, pio0_16);
+pin!(Pin17, 17, pio0_17);
+
+pull_resistors!(Pin0);
+pull_resistors!(Pin1);
+pull_resistors!(Pin2);
+pull_resistors!(Pin3);
+pull_resistors!(Pin4);
+pull_resistors!(Pin5);
+pull_resistors!(Pin6);
+pull_resistors!(Pin7);
+pull_resistors!(Pin8);
+pull_resistors!(Pin9);
+pull_resistors!(Pin12);
+pull_resistors!(Pin13);
+pull_resistors!(Pin14);
+pull_resistors!(Pin15);
+pull_resistors!(Pin16);
+pull_resistors!(Pin17);
+
+true_open_drain_input!(Pin10);
+true_open_drain_input!(Pin11);
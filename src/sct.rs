@@ -0,0 +1,45 @@
+//! Ownership of the state configurable timer (SCT).
+//!
+//! This crate does not yet provide a general-purpose driver for the SCT.
+//! The `SCT` object in `Peripherals` represents ownership of the timer, so
+//! that the drivers in this crate that use it internally, such as the
+//! RC-timing mode of `cmp::adc`, can take it and be sure that nothing else
+//! is using it at the same time.
+
+use core::marker::PhantomData;
+
+/// Represents the state configurable timer.
+pub struct SCT(PhantomData<()>);
+
+/// The SCT object represents access to a single system peripheral, so it's
+/// not safe to share it across multiple threads without some external
+/// concurrency control mechanisms.
+impl !Sync for SCT {}
+
+impl SCT {
+    pub(crate) fn new() -> Self {
+        Self(PhantomData)
+    }
+
+    // Enables the SCT's bus clock and resets the timer, returning all of its
+    // registers to their reset values.
+    pub(crate) fn power_up(&mut self) {
+        let syscon = lpc81x_pac::SYSCON::ptr();
+        unsafe {
+            (*syscon).sysahbclkctrl.modify(|_, w| w.sct().enable());
+            (*syscon)
+                .presetctrl
+                .modify(|_, w| w.sct_rst_n().assert_the_sct_reset());
+            (*syscon)
+                .presetctrl
+                .modify(|_, w| w.sct_rst_n().clear_the_sct_reset_());
+        }
+        cortex_m::asm::dsb();
+    }
+
+    // Disables the SCT's bus clock.
+    pub(crate) fn power_down(&mut self) {
+        let syscon = lpc81x_pac::SYSCON::ptr();
+        unsafe { (*syscon).sysahbclkctrl.modify(|_, w| w.sct().disable()) }
+    }
+}
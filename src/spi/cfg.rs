@@ -17,3 +17,41 @@ pub enum Polarity {
     ActiveLow,
     ActiveHigh,
 }
+
+/// Controls how a word sent with `send_controlled` relates to the words
+/// around it.
+#[derive(Clone, Copy, Debug)]
+pub struct Control {
+    /// Deasserts SSEL after this word, ending the transfer.
+    pub end_of_transfer: bool,
+
+    /// Marks this word as the last of a frame, so that the frame delay is
+    /// inserted before the next word.
+    pub end_of_frame: bool,
+
+    /// Discards the word received while this one is sent, so that it
+    /// need not be read.
+    pub ignore_received: bool,
+}
+
+/// Delays inserted by the hardware around transfers in host mode, each
+/// measured in SPI clock cycles from 0 to 15.
+///
+/// Use `delay_cycles_for_ns` on an SPI peripheral in host mode to convert
+/// a time into a number of cycles at its current clock rate.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Delays {
+    /// Time between SSEL being asserted and the start of the first frame.
+    pub pre_delay: u8,
+
+    /// Time between the end of the last frame and SSEL being deasserted.
+    pub post_delay: u8,
+
+    /// Time between frames within a transfer, after each word sent with
+    /// the end-of-frame flag.
+    pub frame_delay: u8,
+
+    /// Time that SSEL stays deasserted between transfers, in addition to
+    /// the one cycle that the hardware always inserts.
+    pub transfer_delay: u8,
+}
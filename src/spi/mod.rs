@@ -1,6 +1,7 @@
 //! Interface to the SPI peripherals.
 
 use crate::pins;
+use crate::wakeup;
 use core::marker::PhantomData;
 
 pub mod cfg;
@@ -11,6 +12,7 @@ macro_rules! spi_device {
     ($typename:ident, $fieldname:ident, {
         RESETCTRL: $resetctrlfield:ident,
         CLKCTRL: $clkctrlfield:ident,
+        WAKEUP: $wakeupbit:expr,
         SCLK: ($sclkassign:ident, $sclkfield:ident),
         MOSI: ($mosiassign:ident, $mosifield:ident),
         MISO: ($misoassign:ident, $misofield:ident),
@@ -187,10 +189,20 @@ macro_rules! spi_device {
         {
             /// Consumes the inactive SPI bus and returns it with host mode enabled,
             /// using the given pin for SCLK.
+            ///
+            /// If a frequency in Hz is given along with the frozen clock
+            /// configuration, the SPI clock is set as for `set_frequency`
+            /// before the SCLK pin is assigned. Otherwise, SCLK runs at the
+            /// system clock rate until changed.
+            ///
+            /// Panics if a frequency is given that is slower than the SPI
+            /// clock can run, so that the bus is never used faster than
+            /// intended.
             pub fn activate_as_host<SCLK: pins::UnassignedPin>(
                 self,
                 sclk: SCLK,
                 cfg: cfg::Config,
+                frequency: Option<(u32, &crate::clock::Clocks)>,
             ) -> $typename<
                 mode::Host,
                 pins::mode::Assigned<SCLK>,
@@ -200,9 +212,13 @@ macro_rules! spi_device {
             > {
                 Self::set_spi_clock(true);
                 Self::set_enabled(true, true, cfg);
+                let mut ret = $typename::new();
+                if let Some((hz, clocks)) = frequency {
+                    assert!(ret.set_frequency(hz, clocks).is_some());
+                }
                 Self::select_sclk(SCLK::NUMBER);
                 unused(sclk);
-                $typename::new()
+                ret
             }
 
             /// Consumes the inactive SPI bus and returns it with device mode enabled,
@@ -248,7 +264,8 @@ macro_rules! spi_device {
             /// assert and unassert the SSEL signal to delimit the transaction.
             /// When using this trait implementation, implement chip select as
             /// a generic digital output pin instead, because that is what
-            /// embedded-hal device drivers expect.
+            /// embedded-hal device drivers expect, or use the `Transactional`
+            /// trait to have the hardware drive an assigned SSEL pin.
             fn send(&mut self, word: W) -> Result<(), nb::Error<!>> {
                 let periph = lpc81x_pac::$typename::ptr();
                 let stat = unsafe { (*periph).stat.read() };
@@ -259,6 +276,8 @@ macro_rules! spi_device {
                     (*periph).txdatctl.write(|w| {
                         w.txdat()
                             .bits(word.value_to_transmit() & W::MASK)
+                            .txsseln()
+                            .ssel_not_asserted_()
                             .flen()
                             .bits(W::LEN - 1)
                     });
@@ -314,6 +333,124 @@ macro_rules! spi_device {
         {
         }
 
+        impl<SCLK, MOSI, MISO, SSEL>
+            $typename<mode::Host, SCLK, MOSI, MISO, pins::mode::Assigned<SSEL>>
+        where
+            SCLK: pins::PinAssignment,
+            MOSI: pins::PinAssignment,
+            MISO: pins::PinAssignment,
+            SSEL: pins::Pin,
+        {
+            /// Sends a word (from 1 to 16 bits) with the SSEL signal
+            /// asserted, as part of a transfer delimited by the hardware.
+            ///
+            /// SSEL remains asserted after this word unless the control
+            /// settings end the transfer. Unless the control settings ignore
+            /// the received word, it must then be read using `read` from the
+            /// `FullDuplex` trait.
+            ///
+            /// Most callers should use the `Transactional` trait instead,
+            /// which manages the control settings automatically.
+            pub fn send_controlled<W: word::Word>(
+                &mut self,
+                word: &W,
+                control: cfg::Control,
+            ) -> nb::Result<(), !> {
+                let periph = lpc81x_pac::$typename::ptr();
+                let stat = unsafe { (*periph).stat.read() };
+                if stat.txrdy().bit_is_clear() {
+                    return Err(nb::Error::WouldBlock);
+                }
+                unsafe {
+                    (*periph).txdatctl.write(|w| {
+                        w.txdat()
+                            .bits(word.value_to_transmit() & W::MASK)
+                            .txsseln()
+                            .ssel_asserted_()
+                            .eot()
+                            .bit(control.end_of_transfer)
+                            .eof()
+                            .bit(control.end_of_frame)
+                            .rxignore()
+                            .bit(control.ignore_received)
+                            .flen()
+                            .bits(W::LEN - 1)
+                    });
+                };
+                Ok(())
+            }
+        }
+
+        impl<W, SCLK, MOSI, MISO, SSEL> embedded_hal::blocking::spi::Transactional<W>
+            for $typename<mode::Host, SCLK, MOSI, MISO, pins::mode::Assigned<SSEL>>
+        where
+            W: word::Word + 'static,
+            SCLK: pins::PinAssignment,
+            MOSI: pins::PinAssignment,
+            MISO: pins::PinAssignment,
+            SSEL: pins::Pin,
+        {
+            type Error = !;
+
+            /// Executes the given operations as a single transfer, with the
+            /// hardware SSEL signal asserted throughout and deasserted after
+            /// the last word.
+            ///
+            /// The last word of each operation ends a frame, so the frame
+            /// delay (if any) separates the operations. Words received
+            /// during `Write` operations are ignored.
+            fn exec<'a>(
+                &mut self,
+                operations: &mut [embedded_hal::blocking::spi::Operation<'a, W>],
+            ) -> Result<(), !> {
+                use embedded_hal::blocking::spi::Operation;
+                use embedded_hal::spi::FullDuplex;
+
+                let last_op = match operations.iter().rposition(|op| match op {
+                    Operation::Write(words) => !words.is_empty(),
+                    Operation::Transfer(words) => !words.is_empty(),
+                }) {
+                    Some(idx) => idx,
+                    None => return Ok(()),
+                };
+
+                for (i, op) in operations[..=last_op].iter_mut().enumerate() {
+                    match op {
+                        Operation::Write(words) => {
+                            for (j, word) in words.iter().enumerate() {
+                                let last = j == words.len() - 1;
+                                let control = cfg::Control {
+                                    end_of_transfer: last && i == last_op,
+                                    end_of_frame: last,
+                                    ignore_received: true,
+                                };
+                                nb::block!(self.send_controlled(word, control))?;
+                            }
+                        }
+                        Operation::Transfer(words) => {
+                            let len = words.len();
+                            for (j, word) in words.iter_mut().enumerate() {
+                                let last = j == len - 1;
+                                let control = cfg::Control {
+                                    end_of_transfer: last && i == last_op,
+                                    end_of_frame: last,
+                                    ignore_received: false,
+                                };
+                                nb::block!(self.send_controlled(word, control))?;
+                                *word = nb::block!(FullDuplex::<W>::read(self))?;
+                            }
+                        }
+                    }
+                }
+
+                // Wait for SSEL to be deasserted before returning, so that
+                // the caller can rely on the transfer being complete.
+                let periph = lpc81x_pac::$typename::ptr();
+                while unsafe { (*periph).stat.read().idle().bit_is_clear() } {}
+                Ok(())
+            }
+        }
+
         impl<
                 SCLK: pins::PinAssignment,
                 MOSI: pins::PinAssignment,
@@ -342,12 +479,114 @@ macro_rules! spi_device {
                         .write(|w| w.divval().bits((real_div - 1) as u16))
                 }
             }
+
+            /// Sets the SPI clock to the fastest rate that does not exceed
+            /// the given frequency in Hz, based on the system clock rate in
+            /// the given frozen clock configuration, and returns the rate
+            /// actually achieved.
+            ///
+            /// The SPI clock is the system clock divided by a whole number
+            /// between 1 and 65536, so the result may be lower than requested.
+            /// If the requested frequency is higher than the system clock
+            /// then the SPI clock runs at the system clock rate instead.
+            ///
+            /// Returns `None`, leaving the SPI clock unchanged, if the
+            /// requested frequency is slower than the system clock divided
+            /// by 65536, because every achievable rate would exceed it.
+            pub fn set_frequency(&mut self, hz: u32, clocks: &crate::clock::Clocks) -> Option<u32> {
+                let system = clocks.system_clock();
+                if hz == 0 {
+                    return None;
+                }
+                let div = system / hz + if system % hz != 0 { 1 } else { 0 };
+                if div > 65536 {
+                    return None;
+                }
+                self.set_clock_divider(div);
+                Some(system / core::cmp::max(div, 1))
+            }
+
+            /// Sets the delays that the hardware inserts around transfers
+            /// and frames, as given in SPI clock cycles.
+            ///
+            /// Panics if any delay is greater than 15, which is the longest
+            /// delay the hardware supports.
+            pub fn set_delays(&mut self, delays: cfg::Delays) {
+                assert!(
+                    delays.pre_delay <= 15
+                        && delays.post_delay <= 15
+                        && delays.frame_delay <= 15
+                        && delays.transfer_delay <= 15
+                );
+                let periph = lpc81x_pac::$typename::ptr();
+                unsafe {
+                    (*periph).dly.write(|w| {
+                        w.pre_delay()
+                            .bits(delays.pre_delay)
+                            .post_delay()
+                            .bits(delays.post_delay)
+                            .frame_delay()
+                            .bits(delays.frame_delay)
+                            .transfer_delay()
+                            .bits(delays.transfer_delay)
+                    })
+                }
+            }
+
+            /// Returns the smallest number of SPI clock cycles that lasts at
+            /// least the given number of nanoseconds at the current clock
+            /// divider, for use in `cfg::Delays`.
+            ///
+            /// Returns `None` if more than 15 cycles would be needed, which
+            /// is longer than the hardware can delay at the current clock
+            /// rate.
+            pub fn delay_cycles_for_ns(
+                &self,
+                ns: u32,
+                clocks: &crate::clock::Clocks,
+            ) -> Option<u8> {
+                let periph = lpc81x_pac::$typename::ptr();
+                let div = unsafe { (*periph).div.read().divval().bits() } as u64 + 1;
+                let spi_clock = clocks.system_clock() as u64 / div;
+                let cycles = (ns as u64 * spi_clock + 999_999_999) / 1_000_000_000;
+                if cycles > 15 {
+                    None
+                } else {
+                    Some(cycles as u8)
+                }
+            }
         }
 
         /* ******************************
             METHODS FOR DEVICE MODE
         ****************************** */
 
+        impl<
+                SCLK: pins::PinAssignment,
+                MOSI: pins::PinAssignment,
+                MISO: pins::PinAssignment,
+                SSEL: pins::PinAssignment,
+            > $typename<mode::Device, SCLK, MOSI, MISO, SSEL>
+        {
+            /// Allows the SPI peripheral's interrupt to wake the device from
+            /// deep-sleep or power-down mode.
+            ///
+            /// This is available only in device mode, because the host
+            /// drives SCLK and so the peripheral can still receive data while
+            /// the system clock is stopped. The peripheral's interrupt must
+            /// also be enabled for the relevant events in order for it to
+            /// wake the device.
+            pub fn enable_wakeup(&mut self) {
+                wakeup::set_peripheral($wakeupbit, true);
+            }
+
+            /// Prevents the SPI peripheral's interrupt from waking the device
+            /// from deep-sleep or power-down mode.
+            pub fn disable_wakeup(&mut self) {
+                wakeup::set_peripheral($wakeupbit, false);
+            }
+        }
+
         /* ******************************
            METHODS FOR ANY ACTIVE MODE
         ****************************** */
@@ -412,6 +651,13 @@ macro_rules! spi_device {
             > $typename<MODE, SCLK, MOSI, MISO, pins::mode::Unassigned>
         {
             /// Assigns an unassigned external pin to the SSEL signal.
+            ///
+            /// In host mode, the hardware drives SSEL only for words sent
+            /// using `send_controlled` or the `Transactional` trait. Words
+            /// sent using `FullDuplex::send` leave SSEL deasserted. Earlier
+            /// versions of this crate instead asserted SSEL for those words
+            /// and never deasserted it, so code that relied on that should
+            /// switch to `Transactional` or drive chip select from a GPIO pin.
             pub fn with_ssel<SSEL: pins::UnassignedPin>(
                 self,
                 ssel: SSEL,
@@ -569,6 +815,7 @@ macro_rules! spi_device {
 spi_device!(SPI0, spi0, {
     RESETCTRL: spi0_rst_n,
     CLKCTRL: spi0,
+    WAKEUP: wakeup::SPI0,
     SCLK: (pinassign3, spi0_sck_io),
     MOSI: (pinassign4, spi0_mosi_io),
     MISO: (pinassign4, spi0_miso_io),
@@ -577,6 +824,7 @@ spi_device!(SPI0, spi0, {
 spi_device!(SPI1, spi1, {
     RESETCTRL: spi1_rst_n,
     CLKCTRL: spi1,
+    WAKEUP: wakeup::SPI1,
     SCLK: (pinassign4, spi1_sck_io),
     MOSI: (pinassign5, spi1_mosi_io),
     MISO: (pinassign5, spi1_miso_io),
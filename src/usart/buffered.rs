@@ -0,0 +1,334 @@
+//! Interrupt-driven USART with statically-allocated ring buffers.
+//!
+//! The LPC81x USARTs have only a single-character receive buffer, so
+//! polling them from a busy application can easily lose characters. An
+//! active USART can instead be converted into a buffered USART using its
+//! `into_buffered` method, which returns three objects:
+//!
+//! * `Tx`, which queues characters for transmission.
+//! * `Rx`, which takes characters from the receive buffer.
+//! * `Handler`, whose `on_interrupt` method must be called from the
+//!   interrupt service routine for the USART.
+//!
+//! These objects can be moved to different tasks, such as separate RTFM
+//! resources:
+//!
+//! ```rust
+//! static mut TX_BUF: [u8; 32] = [0; 32];
+//! static mut RX_BUF: [u8; 32] = [0; 32];
+//!
+//! let (tx, rx, handler) = serial.into_buffered(
+//!     unsafe { &mut TX_BUF },
+//!     unsafe { &mut RX_BUF },
+//! );
+//!
+//! // ...and then in the USART0 interrupt handler:
+//! handler.on_interrupt();
+//! ```
+//!
+//! Each buffer holds one character less than its length. Received
+//! characters that arrive while the receive buffer is full are discarded
+//! and counted as overruns, along with any characters lost by the USART
+//! itself. Characters received with framing or parity errors are also
+//! discarded and counted. The counts can be read using `Rx::error_counts`.
+//!
+//! The buffers hold eight-bit characters, so the ninth bit is lost if the
+//! USART is configured for nine data bits.
+
+use super::Instance;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicPtr, AtomicU32, AtomicUsize, Ordering};
+
+/// The transmit half of a buffered USART.
+pub struct Tx<U: Instance>(PhantomData<U>);
+
+/// The receive half of a buffered USART.
+pub struct Rx<U: Instance>(PhantomData<U>);
+
+/// The interrupt handler for a buffered USART, which also owns the
+/// underlying USART object while it is buffered.
+pub struct Handler<U: Instance> {
+    usart: U,
+}
+
+/// A buffered USART object represents access to part of a single system
+/// peripheral, so it's not safe to share it across multiple threads without
+/// some external concurrency control mechanisms.
+impl<U: Instance> !Sync for Tx<U> {}
+impl<U: Instance> !Sync for Rx<U> {}
+impl<U: Instance> !Sync for Handler<U> {}
+
+impl<U: Instance> Handler<U> {
+    pub(crate) fn new(
+        usart: U,
+        tx_buffer: &'static mut [u8],
+        rx_buffer: &'static mut [u8],
+    ) -> Self {
+        assert!(tx_buffer.len() >= 2 && rx_buffer.len() >= 2);
+        let state = U::state();
+        let regs = U::registers();
+        unsafe {
+            state.tx.attach(tx_buffer);
+            state.rx.attach(rx_buffer);
+            state.overrun.store(0, Ordering::Relaxed);
+            state.framing.store(0, Ordering::Relaxed);
+            state.parity.store(0, Ordering::Relaxed);
+            (*regs).intenset.write(|w| w.rxrdyen().set_bit());
+            let nvic = lpc81x_pac::NVIC::ptr();
+            (*nvic).iser[0].write(U::NVIC_BITMASK);
+        }
+        Self { usart }
+    }
+
+    /// Services the USART, moving characters between the USART and the
+    /// buffers.
+    ///
+    /// This must be called from the interrupt service routine for the
+    /// USART, or else no characters will be sent or received.
+    pub fn on_interrupt(&mut self) {
+        let state = U::state();
+        let regs = U::registers();
+        let stat = unsafe { (*regs).stat.read() };
+
+        if stat.overrunint().bit_is_set() {
+            unsafe { (*regs).stat.write(|w| w.overrunint().set_bit()) };
+            increment(&state.overrun);
+        }
+
+        if stat.rxrdy().bit_is_set() {
+            let raw = unsafe { (*regs).rxdatastat.read() };
+            if raw.framerr().bit_is_set() {
+                unsafe { (*regs).stat.write(|w| w.framerrint().set_bit()) };
+                increment(&state.framing);
+            } else if raw.parityerr().bit_is_set() {
+                unsafe { (*regs).stat.write(|w| w.parityerrint().set_bit()) };
+                increment(&state.parity);
+            } else if !state.rx.push(raw.rxdat().bits() as u8) {
+                increment(&state.overrun);
+            }
+        }
+
+        if stat.txrdy().bit_is_set() {
+            match state.tx.pop() {
+                Some(word) => unsafe { (*regs).txdata.write(|w| w.txdat().bits(word as u16)) },
+                // Nothing left to send, so we stop asking for TXRDY
+                // interrupts until Tx queues something else.
+                None => unsafe { (*regs).intenclr.write(|w| w.txrdyclr().set_bit()) },
+            }
+        }
+    }
+
+    /// Stops buffering and returns the underlying USART object along with
+    /// the two buffers.
+    ///
+    /// Any characters that are still in the buffers are discarded, so
+    /// callers should normally call `flush` on the `Tx` half first.
+    pub fn release(self, tx: Tx<U>, rx: Rx<U>) -> (U, &'static mut [u8], &'static mut [u8]) {
+        unused(tx);
+        unused(rx);
+        let state = U::state();
+        let regs = U::registers();
+        unsafe {
+            let nvic = lpc81x_pac::NVIC::ptr();
+            (*nvic).icer[0].write(U::NVIC_BITMASK);
+            (*regs)
+                .intenclr
+                .write(|w| w.rxrdyclr().set_bit().txrdyclr().set_bit());
+            (*nvic).icpr[0].write(U::NVIC_BITMASK);
+            (self.usart, state.tx.detach(), state.rx.detach())
+        }
+    }
+}
+
+impl<U: Instance> Tx<U> {
+    pub(crate) fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<U: Instance> embedded_hal::serial::Write<u8> for Tx<U> {
+    type Error = !;
+
+    /// Adds a character to the transmit buffer, if there is space.
+    fn write(&mut self, word: u8) -> nb::Result<(), !> {
+        if !U::state().tx.push(word) {
+            return Err(nb::Error::WouldBlock);
+        }
+        let regs = U::registers();
+        unsafe { (*regs).intenset.write(|w| w.txrdyen().set_bit()) };
+        Ok(())
+    }
+
+    /// Waits until the transmit buffer is empty and all characters have
+    /// been transmitted.
+    fn flush(&mut self) -> nb::Result<(), !> {
+        let regs = U::registers();
+        let stat = unsafe { (*regs).stat.read() };
+        if !U::state().tx.is_empty() || stat.txidle().bit_is_clear() {
+            return Err(nb::Error::WouldBlock);
+        }
+        Ok(())
+    }
+}
+
+impl<U: Instance> embedded_hal::blocking::serial::write::Default<u8> for Tx<U> {}
+
+impl<U: Instance> Rx<U> {
+    pub(crate) fn new() -> Self {
+        Self(PhantomData)
+    }
+
+    /// Returns the number of receive errors of each type since the USART
+    /// was converted into a buffered USART.
+    ///
+    /// The counts wrap around on overflow, so callers interested in new
+    /// errors should compare against a previous result.
+    pub fn error_counts(&self) -> ErrorCounts {
+        let state = U::state();
+        ErrorCounts {
+            overrun: state.overrun.load(Ordering::Relaxed),
+            framing: state.framing.load(Ordering::Relaxed),
+            parity: state.parity.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl<U: Instance> embedded_hal::serial::Read<u8> for Rx<U> {
+    type Error = !;
+
+    /// Takes a character from the receive buffer, if one is available.
+    fn read(&mut self) -> nb::Result<u8, !> {
+        U::state().rx.pop().ok_or(nb::Error::WouldBlock)
+    }
+}
+
+/// Counts of receive errors detected by a buffered USART.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ErrorCounts {
+    /// Characters lost because the receive buffer was full or because the
+    /// interrupt was not serviced quickly enough.
+    pub overrun: u32,
+
+    /// Characters received without a valid stop bit.
+    pub framing: u32,
+
+    /// Characters received with an incorrect parity bit.
+    pub parity: u32,
+}
+
+// The buffer state for one USART, shared between the interrupt handler and
+// the Tx and Rx objects.
+#[doc(hidden)]
+pub struct State {
+    tx: Ring,
+    rx: Ring,
+    overrun: AtomicU32,
+    framing: AtomicU32,
+    parity: AtomicU32,
+}
+
+impl State {
+    pub(crate) const fn new() -> Self {
+        Self {
+            tx: Ring::new(),
+            rx: Ring::new(),
+            overrun: AtomicU32::new(0),
+            framing: AtomicU32::new(0),
+            parity: AtomicU32::new(0),
+        }
+    }
+}
+
+// A single-producer, single-consumer ring buffer over a borrowed static
+// buffer. The head is written only by the producer and the tail only by the
+// consumer, so no read-modify-write atomics are needed, which the Cortex-M0+
+// does not support. One slot is always left empty so that a full buffer can
+// be distinguished from an empty one.
+struct Ring {
+    buf: AtomicPtr<u8>,
+    len: AtomicUsize,
+    head: AtomicUsize,
+    tail: AtomicUsize,
+}
+
+impl Ring {
+    const fn new() -> Self {
+        Self {
+            buf: AtomicPtr::new(core::ptr::null_mut()),
+            len: AtomicUsize::new(0),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        }
+    }
+
+    // The caller must ensure that neither the producer nor the consumer is
+    // active.
+    unsafe fn attach(&self, buf: &'static mut [u8]) {
+        self.head.store(0, Ordering::Relaxed);
+        self.tail.store(0, Ordering::Relaxed);
+        self.len.store(buf.len(), Ordering::Relaxed);
+        self.buf.store(buf.as_mut_ptr(), Ordering::Release);
+    }
+
+    // The caller must ensure that neither the producer nor the consumer is
+    // active, and that a buffer was previously attached.
+    unsafe fn detach(&self) -> &'static mut [u8] {
+        let ptr = self.buf.load(Ordering::Acquire);
+        self.buf.store(core::ptr::null_mut(), Ordering::Relaxed);
+        core::slice::from_raw_parts_mut(ptr, self.len.load(Ordering::Relaxed))
+    }
+
+    fn next(&self, idx: usize) -> usize {
+        // Avoiding the remainder operator here, because the Cortex-M0+ has
+        // no division instruction.
+        let next = idx + 1;
+        if next == self.len.load(Ordering::Relaxed) {
+            0
+        } else {
+            next
+        }
+    }
+
+    fn push(&self, word: u8) -> bool {
+        let head = self.head.load(Ordering::Relaxed);
+        let next = self.next(head);
+        if next == self.tail.load(Ordering::Acquire) {
+            return false;
+        }
+        unsafe {
+            let ptr = self.buf.load(Ordering::Relaxed);
+            core::ptr::write_volatile(ptr.add(head), word);
+        }
+        self.head.store(next, Ordering::Release);
+        true
+    }
+
+    fn pop(&self) -> Option<u8> {
+        let tail = self.tail.load(Ordering::Relaxed);
+        if tail == self.head.load(Ordering::Acquire) {
+            return None;
+        }
+        let word = unsafe {
+            let ptr = self.buf.load(Ordering::Relaxed);
+            core::ptr::read_volatile(ptr.add(tail))
+        };
+        self.tail.store(self.next(tail), Ordering::Release);
+        Some(word)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.tail.load(Ordering::Acquire) == self.head.load(Ordering::Acquire)
+    }
+}
+
+// Only the interrupt handler updates the error counts, so a separate load and
+// store is sufficient.
+fn increment(count: &AtomicU32) {
+    count.store(
+        count.load(Ordering::Relaxed).wrapping_add(1),
+        Ordering::Relaxed,
+    );
+}
+
+#[inline(always)]
+fn unused<T>(_v: T) {}
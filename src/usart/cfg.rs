@@ -0,0 +1,45 @@
+pub(crate) const RESET_CONFIG: Config = Config {
+    data_bits: DataBits::Eight,
+    parity: Parity::None,
+    stop_bits: StopBits::One,
+    address_detect: false,
+};
+
+pub struct Config {
+    pub data_bits: DataBits,
+    pub parity: Parity,
+    pub stop_bits: StopBits,
+
+    /// Starts the receiver in address-detect mode, where it ignores
+    /// characters whose most significant data bit is clear. This is
+    /// normally used with nine data bits on multidrop buses, with the ninth
+    /// bit marking addresses.
+    pub address_detect: bool,
+}
+
+/// The default configuration is eight data bits, no parity, and one stop bit,
+/// without address detection.
+impl Default for Config {
+    fn default() -> Self {
+        RESET_CONFIG
+    }
+}
+
+pub enum DataBits {
+    Seven,
+    Eight,
+
+    /// Nine data bits, which must be read and written using `u16` words.
+    Nine,
+}
+
+pub enum Parity {
+    None,
+    Even,
+    Odd,
+}
+
+pub enum StopBits {
+    One,
+    Two,
+}
@@ -0,0 +1,285 @@
+//! The shared USART peripheral clock.
+//!
+//! All three USARTs are clocked from a single peripheral clock, U_PCLK,
+//! which is derived from the main clock by the UARTCLKDIV divider and then
+//! the fractional rate generator (FRG). Each USART then divides U_PCLK by
+//! 16 times its own baud rate generator (BRG) value to produce its baud
+//! rate.
+//!
+//! Because U_PCLK is shared, it must be chosen to suit all of the USARTs
+//! that will be used. `plan` chooses a U_PCLK setting that gives a small
+//! baud rate error across a set of requested baud rates, and
+//! `ClockConfig::freeze` then applies that setting and returns a
+//! `UsartClock` that the USARTs require for activation. The `ClockConfig`
+//! object can be consumed only once, so U_PCLK cannot change while any
+//! USART is using it.
+//!
+//! Baud rates can be obtained only from the frozen `UsartClock`, so a baud
+//! rate is always computed for the U_PCLK setting that is actually applied.
+//!
+//! ```rust
+//! let clocks = p.clock.freeze();
+//! let plan = hal::usart::clock::plan(&clocks, &[115_200, 9_600]).unwrap();
+//! assert!(plan.max_error_ppm() < 20_000);
+//! let usart_clock = p.usart_clock.freeze(&plan);
+//! let fast = usart_clock.baud_rate(115_200).unwrap();
+//! let slow = usart_clock.baud_rate(9_600).unwrap();
+//! let usart0 = p.usart0.activate(&usart_clock, fast, Default::default());
+//! let usart1 = p.usart1.activate(&usart_clock, slow, Default::default());
+//! ```
+
+use crate::clock;
+use core::marker::PhantomData;
+
+/// The maximum number of baud rates that can be given to `plan`, which is
+/// the number of USARTs.
+pub const MAX_RATES: usize = 3;
+
+/// The not-yet-frozen configuration of the shared USART peripheral clock.
+pub struct ClockConfig(PhantomData<()>);
+
+impl !Sync for ClockConfig {}
+
+impl ClockConfig {
+    pub(crate) fn new() -> Self {
+        Self(PhantomData)
+    }
+
+    /// Consumes the clock configuration, applies the U_PCLK setting from
+    /// the given plan, and returns the frozen USART clock.
+    pub fn freeze(self, plan: &Plan) -> UsartClock {
+        let syscon = lpc81x_pac::SYSCON::ptr();
+        unsafe {
+            (*syscon)
+                .presetctrl
+                .modify(|_, w| w.uartfrg_rst_n().bit(true));
+            // The FRG divides by (1 + MULT / (DIV + 1)), and the user manual
+            // requires DIV to be 255 so that MULT is in 256ths.
+            (*syscon).uartfrgdiv.write(|w| w.div().bits(0xff));
+            (*syscon)
+                .uartfrgmult
+                .write(|w| w.mult().bits(plan.setting.frg_mult));
+            (*syscon)
+                .uartclkdiv
+                .write(|w| w.div().bits(plan.setting.clk_div));
+        }
+        UsartClock {
+            setting: plan.setting,
+        }
+    }
+}
+
+/// The frozen USART peripheral clock setting.
+///
+/// A `UsartClock` can be obtained only by calling `ClockConfig::freeze`,
+/// after which the shared U_PCLK setting can no longer be changed.
+#[derive(Clone, Copy, Debug)]
+pub struct UsartClock {
+    setting: Setting,
+}
+
+impl UsartClock {
+    /// Returns the frequency of U_PCLK in Hz, rounded down.
+    pub fn frequency(&self) -> u32 {
+        self.setting.frequency()
+    }
+
+    /// Returns the baud rate closest to the requested rate that can be
+    /// produced from this clock, for passing to a USART.
+    ///
+    /// Returns `None` if the requested rate is zero or is more than U_PCLK
+    /// divided by 16.
+    pub fn baud_rate(&self, requested: u32) -> Option<BaudRate> {
+        BaudRate::for_clock(self.setting, requested, 16)
+    }
+
+    /// Returns the SCLK rate closest to the requested rate that can be
+    /// produced from this clock, for passing to a USART in synchronous
+    /// master mode.
+    ///
+    /// In synchronous mode the USART does not oversample, so SCLK can be as
+    /// fast as U_PCLK. Returns `None` if the requested rate is zero or is
+    /// more than U_PCLK.
+    pub fn sync_clock_rate(&self, requested: u32) -> Option<BaudRate> {
+        BaudRate::for_clock(self.setting, requested, 1)
+    }
+
+    // Returns the UARTCLKDIV and UARTFRGMULT values for this clock.
+    #[cfg(feature = "panic-usart")]
+    pub(crate) fn dividers(&self) -> (u8, u8) {
+        (self.setting.clk_div, self.setting.frg_mult)
+    }
+}
+
+// A U_PCLK setting, in terms of the main clock frequency and the divider
+// values that produce it.
+#[derive(Clone, Copy, Debug)]
+struct Setting {
+    main: u32,
+    clk_div: u8,
+    frg_mult: u8,
+}
+
+impl Setting {
+    fn frequency(&self) -> u32 {
+        ((self.main as u64) * 256 / self.denominator()) as u32
+    }
+
+    // The main clock frequency multiplied by 256 and divided by this value
+    // gives the U_PCLK frequency.
+    fn denominator(&self) -> u64 {
+        (self.clk_div as u64) * (256 + self.frg_mult as u64)
+    }
+}
+
+/// A U_PCLK setting chosen by `plan`.
+///
+/// The baud rates themselves are obtained from the `UsartClock` returned by
+/// `ClockConfig::freeze` once the plan has been applied.
+#[derive(Clone, Copy, Debug)]
+pub struct Plan {
+    setting: Setting,
+    max_error_ppm: u32,
+}
+
+impl Plan {
+    /// Returns the largest error among the requested baud rates, in parts
+    /// per million of the requested rate.
+    pub fn max_error_ppm(&self) -> u32 {
+        self.max_error_ppm
+    }
+
+    /// Returns the frequency of U_PCLK in Hz that this plan would select,
+    /// rounded down.
+    pub fn frequency(&self) -> u32 {
+        self.setting.frequency()
+    }
+}
+
+/// Chooses a U_PCLK setting that gives a small baud rate error across the
+/// given requested baud rates, using the main clock frequency from the
+/// given clocks.
+///
+/// The fastest requested rate is matched as closely as the fractional rate
+/// generator allows, and among the settings that do so, the one with the
+/// smallest largest error is chosen.
+///
+/// Returns `None` if no rates or more than `MAX_RATES` rates are given, or
+/// if any of the rates is zero or too fast for the main clock.
+pub fn plan(clocks: &clock::Clocks, requested: &[u32]) -> Option<Plan> {
+    if requested.is_empty() || requested.len() > MAX_RATES {
+        return None;
+    }
+    let main = clocks.main_clock();
+    let fastest = *requested.iter().max().unwrap();
+    if fastest == 0 || requested.contains(&0) {
+        return None;
+    }
+
+    // Dividing the main clock by more than is needed for the fastest rate
+    // would only reduce the resolution of the BRG, so we only try divisors
+    // that leave U_PCLK at least 16 times the fastest rate.
+    //
+    // Searching every FRG setting for each divisor would take far too long
+    // without a hardware divider, so instead we compute the one FRG setting
+    // per divisor that makes the fastest rate exact, or as near as the FRG
+    // resolution allows. The FRG can only slow U_PCLK by a factor between 1
+    // and 2, so we take the largest BRG value for the fastest rate that is
+    // reachable, which slows U_PCLK the least and so leaves the most BRG
+    // resolution for the other rates.
+    let max_div = core::cmp::min(255, main / (16 * fastest));
+    let mut best: Option<Plan> = None;
+    for clk_div in 1..=max_div {
+        let brg = main / (clk_div * 16 * fastest);
+        let den = (clk_div as u64) * 16 * (fastest as u64) * (brg as u64);
+        let frg_mult = ((main as u64) * 256 + den / 2) / den - 256;
+        let setting = Setting {
+            main,
+            clk_div: clk_div as u8,
+            frg_mult: core::cmp::min(frg_mult, 255) as u8,
+        };
+        let mut candidate = Plan {
+            setting,
+            max_error_ppm: 0,
+        };
+        let mut ok = true;
+        for r in requested {
+            match BaudRate::for_clock(setting, *r, 16) {
+                Some(rate) => {
+                    candidate.max_error_ppm =
+                        core::cmp::max(candidate.max_error_ppm, rate.error_ppm())
+                }
+                None => ok = false,
+            }
+        }
+        if !ok {
+            continue;
+        }
+        let better = match best {
+            Some(ref prev) => candidate.max_error_ppm < prev.max_error_ppm,
+            None => true,
+        };
+        if better {
+            let exact = candidate.max_error_ppm == 0;
+            best = Some(candidate);
+            if exact {
+                return best;
+            }
+        }
+    }
+    best
+}
+
+/// A baud rate setting for one USART.
+///
+/// A `BaudRate` can be obtained only from `UsartClock::baud_rate` or
+/// `UsartClock::sync_clock_rate`. Only one `UsartClock` can ever exist, so
+/// every baud rate is computed for the U_PCLK setting that is applied.
+#[derive(Clone, Copy, Debug)]
+pub struct BaudRate {
+    brg_val: u16,
+    requested: u32,
+    actual: u32,
+}
+
+impl BaudRate {
+    fn for_clock(setting: Setting, requested: u32, oversampling: u64) -> Option<Self> {
+        if requested == 0 {
+            return None;
+        }
+        // U_PCLK is main * 256 / denominator, and the baud rate is
+        // U_PCLK / (oversampling * brg), so we round to the nearest brg.
+        let num = (setting.main as u64) * 256;
+        let den = setting.denominator() * oversampling;
+        let brg = (num + den * (requested as u64) / 2) / (den * (requested as u64));
+        if !(1..=65536).contains(&brg) {
+            return None;
+        }
+        Some(Self {
+            brg_val: (brg - 1) as u16,
+            requested,
+            actual: (num / (den * brg)) as u32,
+        })
+    }
+
+    /// Returns the requested baud rate.
+    pub fn requested(&self) -> u32 {
+        self.requested
+    }
+
+    /// Returns the actual baud rate, rounded down.
+    pub fn actual(&self) -> u32 {
+        self.actual
+    }
+
+    /// Returns the difference between the actual and requested baud rates,
+    /// in parts per million of the requested rate.
+    pub fn error_ppm(&self) -> u32 {
+        let diff = self.actual.abs_diff(self.requested);
+        ((diff as u64) * 1_000_000 / (self.requested as u64)) as u32
+    }
+
+    pub(crate) fn brg_val(&self) -> u16 {
+        self.brg_val
+    }
+}
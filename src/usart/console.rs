@@ -0,0 +1,79 @@
+//! A text console on top of a USART, implementing `core::fmt::Write`.
+//!
+//! `Console` wraps anything that implements the blocking `embedded-hal`
+//! serial write trait, including an active USART or the `Tx` half of a
+//! buffered USART, and converts each `\n` into `\r\n` as terminals expect.
+//!
+//! ```rust
+//! use core::fmt::Write;
+//!
+//! let mut console = hal::usart::console::Console::new(serial);
+//! console.write_str("hello\n").unwrap();
+//! writeln!(console, "temperature: {}", temp).unwrap();
+//! ```
+//!
+//! The `write!` and `writeln!` macros pull the `core::fmt` formatting
+//! machinery into the program, which takes up a significant part of the
+//! LPC810's 4KB of flash. Programs for the smallest parts can use
+//! `write_str` and `write_u32` instead, which do not.
+
+use core::fmt;
+use embedded_hal::blocking::serial::Write;
+
+/// A text console that writes to a serial transmitter.
+pub struct Console<W: Write<u8>> {
+    serial: W,
+}
+
+impl<W: Write<u8>> Console<W> {
+    /// Creates a console that writes to the given serial transmitter.
+    pub fn new(serial: W) -> Self {
+        Self { serial }
+    }
+
+    /// Consumes the console and returns the serial transmitter it was
+    /// writing to.
+    pub fn release(self) -> W {
+        self.serial
+    }
+
+    /// Writes the given number in decimal, without using `core::fmt`.
+    pub fn write_u32(&mut self, v: u32) -> fmt::Result {
+        let mut buf = [0u8; 10];
+        let digits = format_u32(v, &mut buf);
+        self.serial.bwrite_all(digits).map_err(|_| fmt::Error)
+    }
+
+    /// Waits until everything written so far has been transmitted.
+    pub fn flush(&mut self) -> fmt::Result {
+        self.serial.bflush().map_err(|_| fmt::Error)
+    }
+}
+
+impl<W: Write<u8>> fmt::Write for Console<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for (i, line) in s.split('\n').enumerate() {
+            if i > 0 {
+                self.serial.bwrite_all(b"\r\n").map_err(|_| fmt::Error)?;
+            }
+            self.serial
+                .bwrite_all(line.as_bytes())
+                .map_err(|_| fmt::Error)?;
+        }
+        Ok(())
+    }
+}
+
+// Writes the decimal digits of the given number into the end of the given
+// buffer and returns the slice of it that was used.
+pub(crate) fn format_u32(mut v: u32, buf: &mut [u8; 10]) -> &[u8] {
+    let mut i = buf.len();
+    loop {
+        i -= 1;
+        buf[i] = b'0' + (v % 10) as u8;
+        v /= 10;
+        if v == 0 {
+            return &buf[i..];
+        }
+    }
+}
@@ -0,0 +1,1265 @@
+//! Interface to the USART peripherals.
+//!
+//! The three USARTs share a single peripheral clock, which must be frozen
+//! using the `clock` module before any USART can be activated:
+//!
+//! ```rust
+//! let clocks = p.clock.freeze();
+//! let plan = hal::usart::clock::plan(&clocks, &[115_200]).unwrap();
+//! let usart_clock = p.usart_clock.freeze(&plan);
+//! let serial = p
+//!     .usart0
+//!     .activate(&usart_clock, usart_clock.baud_rate(115_200).unwrap(), Default::default())
+//!     .with_txd(pins.gpio4)
+//!     .with_rxd(pins.gpio0);
+//! ```
+//!
+//! The character format is selected using `cfg::Config`, which defaults to
+//! eight data bits, no parity, and one stop bit:
+//!
+//! ```rust
+//! let cfg = hal::usart::cfg::Config {
+//!     data_bits: hal::usart::cfg::DataBits::Nine,
+//!     parity: hal::usart::cfg::Parity::None,
+//!     stop_bits: hal::usart::cfg::StopBits::One,
+//!     address_detect: true,
+//! };
+//! ```
+
+use crate::pins;
+use crate::wakeup;
+use core::marker::PhantomData;
+
+pub mod buffered;
+pub mod cfg;
+pub mod clock;
+pub mod console;
+pub mod mode;
+#[cfg(feature = "panic-usart")]
+pub mod panic;
+pub mod rs485;
+pub mod word;
+
+macro_rules! usart_device {
+    ($typename:ident, $fieldname:ident, {
+        INDEX: $index:expr,
+        INTERRUPT: $irq:expr,
+        WAKEUP: $wakeupbit:expr,
+        RESETCTRL: $resetctrlfield:ident,
+        CLKCTRL: $clkctrlfield:ident,
+        TXD: ($txdassign:ident, $txdfield:ident),
+        RXD: ($rxdassign:ident, $rxdfield:ident),
+        RTS: ($rtsassign:ident, $rtsfield:ident),
+        CTS: ($ctsassign:ident, $ctsfield:ident),
+        SCLK: ($sclkassign:ident, $sclkfield:ident)
+    }) => {
+        /// Represents the USART peripheral.
+        ///
+        /// Each USART starts in an inactive state, not connected to any
+        /// pins. To use it, call `activate` with the frozen USART clock and
+        /// a baud rate, and then call `with_txd` and `with_rxd` to assign
+        /// external pins for the signals that are needed. For synchronous
+        /// operation, call `activate_sync_master` or `activate_sync_slave`
+        /// instead, and also assign an SCLK pin using `with_sclk`.
+        ///
+        /// An activated USART implements the `embedded-hal` serial traits,
+        /// so you can pass it directly to a device driver that expects any of
+        /// these traits.
+        pub struct $typename<MODE, TXD, RXD, RTS, CTS, SCLK>
+        where
+            MODE: Mode,
+            TXD: pins::PinAssignment,
+            RXD: pins::PinAssignment,
+            RTS: pins::PinAssignment,
+            CTS: pins::PinAssignment,
+            SCLK: pins::PinAssignment,
+        {
+            mode: PhantomData<MODE>,
+            txd: PhantomData<TXD>,
+            rxd: PhantomData<RXD>,
+            rts: PhantomData<RTS>,
+            cts: PhantomData<CTS>,
+            sclk: PhantomData<SCLK>,
+        }
+
+        impl<MODE, TXD, RXD, RTS, CTS, SCLK> $typename<MODE, TXD, RXD, RTS, CTS, SCLK>
+        where
+            MODE: Mode,
+            TXD: pins::PinAssignment,
+            RXD: pins::PinAssignment,
+            RTS: pins::PinAssignment,
+            CTS: pins::PinAssignment,
+            SCLK: pins::PinAssignment,
+        {
+            #[inline(always)]
+            pub(crate) fn new() -> Self {
+                Self {
+                    mode: PhantomData,
+                    txd: PhantomData,
+                    rxd: PhantomData,
+                    rts: PhantomData,
+                    cts: PhantomData,
+                    sclk: PhantomData,
+                }
+            }
+
+            #[inline(always)]
+            fn select_txd(pin: u8) {
+                let swm = lpc81x_pac::SWM::ptr();
+                unsafe { (*swm).$txdassign.modify(|_, w| w.$txdfield().bits(pin)) }
+            }
+
+            #[inline(always)]
+            fn select_rxd(pin: u8) {
+                let swm = lpc81x_pac::SWM::ptr();
+                unsafe { (*swm).$rxdassign.modify(|_, w| w.$rxdfield().bits(pin)) }
+            }
+
+            #[inline(always)]
+            fn select_rts(pin: u8) {
+                let swm = lpc81x_pac::SWM::ptr();
+                unsafe { (*swm).$rtsassign.modify(|_, w| w.$rtsfield().bits(pin)) }
+            }
+
+            #[inline(always)]
+            fn select_cts(pin: u8) {
+                let swm = lpc81x_pac::SWM::ptr();
+                unsafe { (*swm).$ctsassign.modify(|_, w| w.$ctsfield().bits(pin)) }
+            }
+
+            #[inline(always)]
+            fn select_sclk(pin: u8) {
+                let swm = lpc81x_pac::SWM::ptr();
+                unsafe { (*swm).$sclkassign.modify(|_, w| w.$sclkfield().bits(pin)) }
+            }
+
+            #[inline(always)]
+            fn set_enabled(enabled: bool, operation: Operation, cfg: cfg::Config) {
+                let syscon = lpc81x_pac::SYSCON::ptr();
+                let periph = lpc81x_pac::$typename::ptr();
+                unsafe {
+                    if enabled {
+                        // Take the device out of reset first
+                        (*syscon)
+                            .presetctrl
+                            .modify(|_, w| w.$resetctrlfield().bit(true));
+                        cortex_m::asm::dsb();
+                    }
+                    (*periph).cfg.write(|w| {
+                        let w = w.enable().bit(enabled);
+                        let w = match cfg.data_bits {
+                            cfg::DataBits::Seven => w.datalen()._7_bit_data_length_(),
+                            cfg::DataBits::Eight => w.datalen()._8_bit_data_length_(),
+                            cfg::DataBits::Nine => w.datalen()._9_bit_data_length_t(),
+                        };
+                        let w = match cfg.parity {
+                            cfg::Parity::None => w.paritysel().no_parity_(),
+                            cfg::Parity::Even => w.paritysel().even_parity_adds_a_(),
+                            cfg::Parity::Odd => w.paritysel().odd_parity_adds_a_b(),
+                        };
+                        let w = match cfg.stop_bits {
+                            cfg::StopBits::One => w.stoplen()._1_stop_bit_(),
+                            cfg::StopBits::Two => w.stoplen()._2_stop_bits_this_se(),
+                        };
+                        match operation {
+                            Operation::Asynchronous => w,
+                            Operation::SynchronousMaster(edge) => w
+                                .syncen()
+                                .set_bit()
+                                .syncmst()
+                                .set_bit()
+                                .clkpol()
+                                .bit(edge == SampleEdge::Rising),
+                            Operation::SynchronousSlave(edge) => w
+                                .syncen()
+                                .set_bit()
+                                .syncmst()
+                                .clear_bit()
+                                .clkpol()
+                                .bit(edge == SampleEdge::Rising),
+                        }
+                    });
+                    (*periph)
+                        .ctrl
+                        .write(|w| w.addrdet().bit(enabled && cfg.address_detect));
+                    if !enabled {
+                        cortex_m::asm::dsb();
+                        (*syscon)
+                            .presetctrl
+                            .modify(|_, w| w.$resetctrlfield().bit(false));
+                    }
+                }
+            }
+
+            #[inline(always)]
+            fn set_usart_clock(active: bool) {
+                let syscon = lpc81x_pac::SYSCON::ptr();
+                unsafe {
+                    (*syscon).sysahbclkctrl.modify(|_, w| {
+                        if active {
+                            w.$clkctrlfield().enable()
+                        } else {
+                            w.$clkctrlfield().disable()
+                        }
+                    });
+                }
+                cortex_m::asm::dsb();
+            }
+
+            // The USART must be disabled while its configuration changes.
+            #[inline(always)]
+            fn set_cts_enabled(enabled: bool) {
+                let periph = lpc81x_pac::$typename::ptr();
+                unsafe {
+                    (*periph).cfg.modify(|_, w| w.enable().clear_bit());
+                    (*periph).cfg.modify(|_, w| w.ctsen().bit(enabled));
+                    (*periph).cfg.modify(|_, w| w.enable().set_bit());
+                }
+            }
+
+            #[inline(always)]
+            fn set_brg(baud: clock::BaudRate) {
+                let periph = lpc81x_pac::$typename::ptr();
+                unsafe { (*periph).brg.write(|w| w.brgval().bits(baud.brg_val())) }
+            }
+        }
+
+        /// A USART peripheral object represents access to a single system
+        /// peripheral, so it's not safe to share it across multiple threads
+        /// without some external concurrency control mechanisms.
+        impl<MODE, TXD, RXD, RTS, CTS, SCLK> !Sync for $typename<MODE, TXD, RXD, RTS, CTS, SCLK> {}
+
+        /* ******************************
+            METHODS FOR INACTIVE MODE
+        ****************************** */
+
+        impl
+            $typename<
+                mode::Inactive,
+                pins::mode::Unassigned,
+                pins::mode::Unassigned,
+                pins::mode::Unassigned,
+                pins::mode::Unassigned,
+                pins::mode::Unassigned,
+            >
+        {
+            /// Consumes the inactive USART and returns it active in
+            /// asynchronous mode with the given baud rate and character
+            /// format.
+            ///
+            /// The baud rate must have been produced for the given frozen
+            /// USART clock, either by `UsartClock::baud_rate` or by the plan
+            /// that was used to freeze it.
+            pub fn activate(
+                self,
+                usart_clock: &clock::UsartClock,
+                baud: clock::BaudRate,
+                cfg: cfg::Config,
+            ) -> $typename<
+                mode::Asynchronous,
+                pins::mode::Unassigned,
+                pins::mode::Unassigned,
+                pins::mode::Unassigned,
+                pins::mode::Unassigned,
+                pins::mode::Unassigned,
+            > {
+                unused(usart_clock);
+                Self::set_usart_clock(true);
+                Self::set_enabled(true, Operation::Asynchronous, cfg);
+                Self::set_brg(baud);
+                $typename::new()
+            }
+
+            /// Consumes the inactive USART and returns it active in
+            /// synchronous master mode, generating SCLK at the given rate.
+            ///
+            /// The rate must have been produced for the given frozen USART
+            /// clock by `UsartClock::sync_clock_rate`. Received data is
+            /// sampled on the given edge of SCLK.
+            ///
+            /// Call `with_sclk` on the result to assign the SCLK signal to an
+            /// external pin.
+            pub fn activate_sync_master(
+                self,
+                usart_clock: &clock::UsartClock,
+                rate: clock::BaudRate,
+                sample_edge: SampleEdge,
+                cfg: cfg::Config,
+            ) -> $typename<
+                mode::SynchronousMaster,
+                pins::mode::Unassigned,
+                pins::mode::Unassigned,
+                pins::mode::Unassigned,
+                pins::mode::Unassigned,
+                pins::mode::Unassigned,
+            > {
+                unused(usart_clock);
+                Self::set_usart_clock(true);
+                Self::set_enabled(true, Operation::SynchronousMaster(sample_edge), cfg);
+                Self::set_brg(rate);
+                $typename::new()
+            }
+
+            /// Consumes the inactive USART and returns it active in
+            /// synchronous slave mode, clocked by an external SCLK signal.
+            ///
+            /// Received data is sampled on the given edge of SCLK. Call
+            /// `with_sclk` on the result to assign the SCLK signal to an
+            /// external pin.
+            pub fn activate_sync_slave(
+                self,
+                usart_clock: &clock::UsartClock,
+                sample_edge: SampleEdge,
+                cfg: cfg::Config,
+            ) -> $typename<
+                mode::SynchronousSlave,
+                pins::mode::Unassigned,
+                pins::mode::Unassigned,
+                pins::mode::Unassigned,
+                pins::mode::Unassigned,
+                pins::mode::Unassigned,
+            > {
+                unused(usart_clock);
+                Self::set_usart_clock(true);
+                Self::set_enabled(true, Operation::SynchronousSlave(sample_edge), cfg);
+                $typename::new()
+            }
+        }
+
+        /* ******************************
+        METHODS FOR SYNCHRONOUS MASTER MODE
+        ****************************** */
+
+        impl<W, TXD, RXD, RTS, CTS, SCLK> embedded_hal::spi::FullDuplex<W>
+            for $typename<mode::SynchronousMaster, TXD, RXD, RTS, CTS, SCLK>
+        where
+            W: word::Word,
+            TXD: pins::PinAssignment,
+            RXD: pins::PinAssignment,
+            RTS: pins::PinAssignment,
+            CTS: pins::PinAssignment,
+            SCLK: pins::PinAssignment,
+        {
+            type Error = Error;
+
+            /// Sends a character on TXD, generating SCLK pulses to clock it
+            /// out and to clock in a character on RXD at the same time.
+            ///
+            /// As with SPI, any chip select signal must be managed
+            /// separately by calling code, using a generic digital output
+            /// pin.
+            fn send(&mut self, word: W) -> nb::Result<(), Error> {
+                let periph = lpc81x_pac::$typename::ptr();
+                let stat = unsafe { (*periph).stat.read() };
+                if stat.txrdy().bit_is_clear() {
+                    return Err(nb::Error::WouldBlock);
+                }
+                let raw = word.value_to_transmit();
+                unsafe { (*periph).txdata.write(|w| w.txdat().bits(raw)) };
+                Ok(())
+            }
+
+            /// Reads the character that was clocked in during a `send`.
+            ///
+            /// Calling `read` once for every `send` is mandatory, or else
+            /// the next `read` will report an overrun.
+            fn read(&mut self) -> nb::Result<W, Error> {
+                let periph = lpc81x_pac::$typename::ptr();
+                let stat = unsafe { (*periph).stat.read() };
+                if stat.overrunint().bit_is_set() {
+                    unsafe { (*periph).stat.write(|w| w.overrunint().set_bit()) };
+                    return Err(nb::Error::Other(Error::Overrun));
+                }
+                if stat.rxrdy().bit_is_clear() {
+                    return Err(nb::Error::WouldBlock);
+                }
+                let raw = unsafe { (*periph).rxdata.read().rxdat().bits() };
+                Ok(W::from_received(raw))
+            }
+        }
+
+        impl<W, TXD, RXD, RTS, CTS, SCLK> embedded_hal::blocking::spi::write::Default<W>
+            for $typename<mode::SynchronousMaster, TXD, RXD, RTS, CTS, SCLK>
+        where
+            W: word::Word,
+            TXD: pins::PinAssignment,
+            RXD: pins::PinAssignment,
+            RTS: pins::PinAssignment,
+            CTS: pins::PinAssignment,
+            SCLK: pins::PinAssignment,
+        {
+        }
+
+        impl<W, TXD, RXD, RTS, CTS, SCLK> embedded_hal::blocking::spi::write_iter::Default<W>
+            for $typename<mode::SynchronousMaster, TXD, RXD, RTS, CTS, SCLK>
+        where
+            W: word::Word,
+            TXD: pins::PinAssignment,
+            RXD: pins::PinAssignment,
+            RTS: pins::PinAssignment,
+            CTS: pins::PinAssignment,
+            SCLK: pins::PinAssignment,
+        {
+        }
+
+        impl<W, TXD, RXD, RTS, CTS, SCLK> embedded_hal::blocking::spi::transfer::Default<W>
+            for $typename<mode::SynchronousMaster, TXD, RXD, RTS, CTS, SCLK>
+        where
+            W: word::Word,
+            TXD: pins::PinAssignment,
+            RXD: pins::PinAssignment,
+            RTS: pins::PinAssignment,
+            CTS: pins::PinAssignment,
+            SCLK: pins::PinAssignment,
+        {
+        }
+
+        /* ******************************
+        METHODS FOR SYNCHRONOUS SLAVE MODE
+        ****************************** */
+
+        impl<TXD, RXD, RTS, CTS, SCLK> $typename<mode::SynchronousSlave, TXD, RXD, RTS, CTS, SCLK>
+        where
+            TXD: pins::PinAssignment,
+            RXD: pins::PinAssignment,
+            RTS: pins::PinAssignment,
+            CTS: pins::PinAssignment,
+            SCLK: pins::PinAssignment,
+        {
+            /// Allows the USART's interrupt to wake the device from
+            /// deep-sleep or power-down mode.
+            ///
+            /// This is available only in synchronous slave mode, because the
+            /// master drives SCLK and so the USART can still receive data
+            /// while the system clock is stopped. The USART's interrupt must
+            /// also be enabled in order for it to wake the device.
+            pub fn enable_wakeup(&mut self) {
+                wakeup::set_peripheral($wakeupbit, true);
+            }
+
+            /// Prevents the USART's interrupt from waking the device from
+            /// deep-sleep or power-down mode.
+            pub fn disable_wakeup(&mut self) {
+                wakeup::set_peripheral($wakeupbit, false);
+            }
+        }
+
+        /* ******************************
+          METHODS FOR SYNCHRONOUS MODES
+        ****************************** */
+
+        impl<MODE, TXD, RXD, RTS, CTS> $typename<MODE, TXD, RXD, RTS, CTS, pins::mode::Unassigned>
+        where
+            MODE: mode::Synchronous,
+            TXD: pins::PinAssignment,
+            RXD: pins::PinAssignment,
+            RTS: pins::PinAssignment,
+            CTS: pins::PinAssignment,
+        {
+            /// Assigns an unassigned external pin to the SCLK signal.
+            ///
+            /// SCLK is an output in synchronous master mode and an input in
+            /// synchronous slave mode.
+            pub fn with_sclk<SCLK: pins::UnassignedPin>(
+                self,
+                sclk: SCLK,
+            ) -> $typename<MODE, TXD, RXD, RTS, CTS, pins::mode::Assigned<SCLK>> {
+                Self::select_sclk(SCLK::NUMBER);
+                unused(sclk);
+                $typename::new()
+            }
+        }
+
+        impl<MODE, TXD, RXD, RTS, CTS, SCLK>
+            $typename<MODE, TXD, RXD, RTS, CTS, pins::mode::Assigned<SCLK>>
+        where
+            MODE: mode::Synchronous,
+            TXD: pins::PinAssignment,
+            RXD: pins::PinAssignment,
+            RTS: pins::PinAssignment,
+            CTS: pins::PinAssignment,
+            SCLK: pins::Pin,
+        {
+            /// Consumes the USART object and returns a new object with the
+            /// SCLK pin detached.
+            ///
+            /// Along with that new object, the former SCLK pin is also
+            /// returned in unassigned mode, ready to be assigned to another
+            /// function.
+            pub fn release_sclk(
+                self,
+            ) -> (
+                $typename<MODE, TXD, RXD, RTS, CTS, pins::mode::Unassigned>,
+                SCLK,
+            ) {
+                Self::select_sclk(pins::PINASSIGN_NOTHING);
+                ($typename::new(), pin_type_as_is())
+            }
+        }
+
+        /* ******************************
+           METHODS FOR ANY ACTIVE MODE
+        ****************************** */
+
+        impl<MODE, TXD, RXD, RTS, CTS, SCLK> $typename<MODE, TXD, RXD, RTS, CTS, SCLK>
+        where
+            MODE: mode::Active,
+            TXD: pins::PinAssignment,
+            RXD: pins::PinAssignment,
+            RTS: pins::PinAssignment,
+            CTS: pins::PinAssignment,
+            SCLK: pins::PinAssignment,
+        {
+            /// Changes the baud rate.
+            ///
+            /// The baud rate must have been produced for the frozen USART
+            /// clock that was used to activate this USART.
+            pub fn set_baud_rate(&mut self, baud: clock::BaudRate) {
+                Self::set_brg(baud);
+            }
+
+            /// Enables or disables address-detect mode, in which the
+            /// receiver ignores characters whose most significant data bit
+            /// is clear.
+            ///
+            /// On a multidrop bus, software normally disables address-detect
+            /// mode after receiving its own address, and enables it again
+            /// after receiving any other address.
+            pub fn set_address_detect(&mut self, enabled: bool) {
+                let periph = lpc81x_pac::$typename::ptr();
+                unsafe { (*periph).ctrl.modify(|_, w| w.addrdet().bit(enabled)) }
+            }
+
+            /// Starts sending a break, holding TXD low until `end_break` is
+            /// called.
+            ///
+            /// Any character already being transmitted is completed first,
+            /// so this may block for up to one character time.
+            pub fn begin_break(&mut self) {
+                let periph = lpc81x_pac::$typename::ptr();
+                unsafe {
+                    (*periph).ctrl.modify(|_, w| w.txdis().set_bit());
+                    while (*periph).stat.read().txdisint().bit_is_clear() {}
+                    (*periph).ctrl.modify(|_, w| w.txbrken().set_bit());
+                }
+            }
+
+            /// Stops sending a break that was started with `begin_break`,
+            /// and resumes normal transmission.
+            pub fn end_break(&mut self) {
+                let periph = lpc81x_pac::$typename::ptr();
+                unsafe {
+                    (*periph)
+                        .ctrl
+                        .modify(|_, w| w.txbrken().clear_bit().txdis().clear_bit());
+                }
+            }
+
+            /// Sends a break lasting for the given number of microseconds,
+            /// blocking until it is complete.
+            ///
+            /// For example, LIN requires a break of at least 13 bit times
+            /// and DMX512 a break of at least 92µs.
+            pub fn send_break<D>(&mut self, delay: &mut D, duration_us: u32)
+            where
+                D: embedded_hal::blocking::delay::DelayUs<u32>,
+            {
+                self.begin_break();
+                delay.delay_us(duration_us);
+                self.end_break();
+            }
+
+            /// Returns true if the receiver is currently detecting a break,
+            /// meaning that RXD has been low for longer than a whole
+            /// character.
+            ///
+            /// The start of a break is also reported to `read` as a framing
+            /// error.
+            pub fn rx_break(&self) -> bool {
+                let periph = lpc81x_pac::$typename::ptr();
+                unsafe { (*periph).stat.read().rxbrk().bit_is_set() }
+            }
+
+            /// Returns true if a break has started or ended since the last
+            /// call, and clears the notification.
+            ///
+            /// Call `rx_break` to find out whether the break has started or
+            /// ended.
+            pub fn take_rx_break_change(&mut self) -> bool {
+                let periph = lpc81x_pac::$typename::ptr();
+                unsafe {
+                    let changed = (*periph).stat.read().deltarxbrk().bit_is_set();
+                    if changed {
+                        (*periph).stat.write(|w| w.deltarxbrk().set_bit());
+                    }
+                    changed
+                }
+            }
+
+            /// Enables the USART interrupt for the start and end of breaks.
+            ///
+            /// The interrupt service routine must call
+            /// `take_rx_break_change` or else it will be immediately called
+            /// again as soon as it returns.
+            pub fn enable_break_interrupt(&mut self) {
+                let nvic = lpc81x_pac::NVIC::ptr();
+                let periph = lpc81x_pac::$typename::ptr();
+                unsafe {
+                    (*periph).intenset.write(|w| w.deltarxbrken().set_bit());
+                    (*nvic).iser[0].write(<Self as Instance>::NVIC_BITMASK);
+                }
+            }
+
+            /// Disables the USART interrupt for the start and end of breaks.
+            pub fn disable_break_interrupt(&mut self) {
+                let nvic = lpc81x_pac::NVIC::ptr();
+                let periph = lpc81x_pac::$typename::ptr();
+                unsafe {
+                    (*periph).intenclr.write(|w| w.deltarxbrkclr().set_bit());
+                    (*nvic).icer[0].write(<Self as Instance>::NVIC_BITMASK);
+                }
+            }
+        }
+
+        impl<MODE, RXD, RTS, CTS, SCLK> $typename<MODE, pins::mode::Unassigned, RXD, RTS, CTS, SCLK>
+        where
+            MODE: mode::Active,
+            RXD: pins::PinAssignment,
+            RTS: pins::PinAssignment,
+            CTS: pins::PinAssignment,
+            SCLK: pins::PinAssignment,
+        {
+            /// Assigns an unassigned external pin to the TXD signal.
+            pub fn with_txd<TXD: pins::UnassignedPin>(
+                self,
+                txd: TXD,
+            ) -> $typename<MODE, pins::mode::Assigned<TXD>, RXD, RTS, CTS, SCLK> {
+                Self::select_txd(TXD::NUMBER);
+                unused(txd);
+                $typename::new()
+            }
+        }
+
+        impl<MODE, TXD, RTS, CTS, SCLK> $typename<MODE, TXD, pins::mode::Unassigned, RTS, CTS, SCLK>
+        where
+            MODE: mode::Active,
+            TXD: pins::PinAssignment,
+            RTS: pins::PinAssignment,
+            CTS: pins::PinAssignment,
+            SCLK: pins::PinAssignment,
+        {
+            /// Assigns an unassigned external pin to the RXD signal.
+            pub fn with_rxd<RXD: pins::UnassignedPin>(
+                self,
+                rxd: RXD,
+            ) -> $typename<MODE, TXD, pins::mode::Assigned<RXD>, RTS, CTS, SCLK> {
+                Self::select_rxd(RXD::NUMBER);
+                unused(rxd);
+                $typename::new()
+            }
+        }
+
+        impl<MODE, TXD, RXD, RTS, CTS, SCLK>
+            $typename<MODE, pins::mode::Assigned<TXD>, RXD, RTS, CTS, SCLK>
+        where
+            MODE: mode::Active,
+            TXD: pins::Pin,
+            RXD: pins::PinAssignment,
+            RTS: pins::PinAssignment,
+            CTS: pins::PinAssignment,
+            SCLK: pins::PinAssignment,
+        {
+            /// Consumes the USART object and returns a new object with the
+            /// TXD pin detached.
+            ///
+            /// Along with that new object, the former TXD pin is also
+            /// returned in unassigned mode, ready to be assigned to another
+            /// function.
+            pub fn release_txd(
+                self,
+            ) -> (
+                $typename<MODE, pins::mode::Unassigned, RXD, RTS, CTS, SCLK>,
+                TXD,
+            ) {
+                Self::select_txd(pins::PINASSIGN_NOTHING);
+                ($typename::new(), pin_type_as_is())
+            }
+        }
+
+        impl<MODE, TXD, RXD, RTS, CTS, SCLK>
+            $typename<MODE, TXD, pins::mode::Assigned<RXD>, RTS, CTS, SCLK>
+        where
+            MODE: mode::Active,
+            TXD: pins::PinAssignment,
+            RXD: pins::Pin,
+            RTS: pins::PinAssignment,
+            CTS: pins::PinAssignment,
+            SCLK: pins::PinAssignment,
+        {
+            /// Consumes the USART object and returns a new object with the
+            /// RXD pin detached.
+            ///
+            /// Along with that new object, the former RXD pin is also
+            /// returned in unassigned mode, ready to be assigned to another
+            /// function.
+            pub fn release_rxd(
+                self,
+            ) -> (
+                $typename<MODE, TXD, pins::mode::Unassigned, RTS, CTS, SCLK>,
+                RXD,
+            ) {
+                Self::select_rxd(pins::PINASSIGN_NOTHING);
+                ($typename::new(), pin_type_as_is())
+            }
+        }
+
+        impl<MODE, TXD, RXD, CTS, SCLK> $typename<MODE, TXD, RXD, pins::mode::Unassigned, CTS, SCLK>
+        where
+            MODE: mode::Active,
+            TXD: pins::PinAssignment,
+            RXD: pins::PinAssignment,
+            CTS: pins::PinAssignment,
+            SCLK: pins::PinAssignment,
+        {
+            /// Assigns an unassigned external pin to the RTS signal.
+            ///
+            /// The USART deasserts RTS (drives it high) while it has a
+            /// received character that has not yet been read, to ask the
+            /// remote transmitter to pause.
+            pub fn with_rts<RTS: pins::UnassignedPin>(
+                self,
+                rts: RTS,
+            ) -> $typename<MODE, TXD, RXD, pins::mode::Assigned<RTS>, CTS, SCLK> {
+                Self::select_rts(RTS::NUMBER);
+                unused(rts);
+                $typename::new()
+            }
+        }
+
+        impl<MODE, TXD, RXD, RTS, SCLK> $typename<MODE, TXD, RXD, RTS, pins::mode::Unassigned, SCLK>
+        where
+            MODE: mode::Active,
+            TXD: pins::PinAssignment,
+            RXD: pins::PinAssignment,
+            RTS: pins::PinAssignment,
+            SCLK: pins::PinAssignment,
+        {
+            /// Assigns an unassigned external pin to the CTS signal and
+            /// enables CTS flow control.
+            ///
+            /// While CTS is deasserted (high), the USART finishes any
+            /// character already being transmitted but does not start
+            /// another.
+            pub fn with_cts<CTS: pins::UnassignedPin>(
+                self,
+                cts: CTS,
+            ) -> $typename<MODE, TXD, RXD, RTS, pins::mode::Assigned<CTS>, SCLK> {
+                Self::select_cts(CTS::NUMBER);
+                Self::set_cts_enabled(true);
+                unused(cts);
+                $typename::new()
+            }
+        }
+
+        impl<MODE, TXD, RXD, RTS, CTS, SCLK>
+            $typename<MODE, TXD, RXD, pins::mode::Assigned<RTS>, CTS, SCLK>
+        where
+            MODE: mode::Active,
+            TXD: pins::PinAssignment,
+            RXD: pins::PinAssignment,
+            RTS: pins::Pin,
+            CTS: pins::PinAssignment,
+            SCLK: pins::PinAssignment,
+        {
+            /// Consumes the USART object and returns a new object with the
+            /// RTS pin detached.
+            ///
+            /// Along with that new object, the former RTS pin is also
+            /// returned in unassigned mode, ready to be assigned to another
+            /// function.
+            pub fn release_rts(
+                self,
+            ) -> (
+                $typename<MODE, TXD, RXD, pins::mode::Unassigned, CTS, SCLK>,
+                RTS,
+            ) {
+                Self::select_rts(pins::PINASSIGN_NOTHING);
+                ($typename::new(), pin_type_as_is())
+            }
+        }
+
+        impl<MODE, TXD, RXD, RTS, CTS, SCLK>
+            $typename<MODE, TXD, RXD, RTS, pins::mode::Assigned<CTS>, SCLK>
+        where
+            MODE: mode::Active,
+            TXD: pins::PinAssignment,
+            RXD: pins::PinAssignment,
+            RTS: pins::PinAssignment,
+            CTS: pins::Pin,
+            SCLK: pins::PinAssignment,
+        {
+            /// Consumes the USART object and returns a new object with the
+            /// CTS pin detached and CTS flow control disabled.
+            ///
+            /// Along with that new object, the former CTS pin is also
+            /// returned in unassigned mode, ready to be assigned to another
+            /// function.
+            pub fn release_cts(
+                self,
+            ) -> (
+                $typename<MODE, TXD, RXD, RTS, pins::mode::Unassigned, SCLK>,
+                CTS,
+            ) {
+                Self::set_cts_enabled(false);
+                Self::select_cts(pins::PINASSIGN_NOTHING);
+                ($typename::new(), pin_type_as_is())
+            }
+        }
+
+        impl<MODE, TXD, RXD, RTS, CTS, SCLK> $typename<MODE, TXD, RXD, RTS, CTS, SCLK>
+        where
+            MODE: mode::Active,
+            TXD: pins::PinAssignment,
+            RXD: pins::PinAssignment,
+            RTS: pins::PinAssignment,
+            CTS: pins::PinAssignment,
+            SCLK: pins::PinAssignment,
+        {
+            /// Consumes the USART and returns it as an interrupt-driven
+            /// buffered USART, using the given static buffers for
+            /// transmitting and receiving.
+            ///
+            /// The USART interrupt is enabled immediately, so the interrupt
+            /// service routine must call `on_interrupt` on the returned
+            /// handler. Each buffer must be at least two bytes long.
+            ///
+            /// Use `buffered::Handler::release` to recover the USART object
+            /// and the buffers.
+            pub fn into_buffered(
+                self,
+                tx_buffer: &'static mut [u8],
+                rx_buffer: &'static mut [u8],
+            ) -> (
+                buffered::Tx<Self>,
+                buffered::Rx<Self>,
+                buffered::Handler<Self>,
+            ) {
+                let handler = buffered::Handler::new(self, tx_buffer, rx_buffer);
+                (buffered::Tx::new(), buffered::Rx::new(), handler)
+            }
+        }
+
+        #[cfg(feature = "panic-usart")]
+        impl<TXD, RXD, RTS, CTS, SCLK>
+            $typename<mode::Asynchronous, pins::mode::Assigned<TXD>, RXD, RTS, CTS, SCLK>
+        where
+            TXD: pins::Pin,
+            RXD: pins::PinAssignment,
+            RTS: pins::PinAssignment,
+            CTS: pins::PinAssignment,
+            SCLK: pins::PinAssignment,
+        {
+            /// Selects this USART and its TXD pin for reporting panics, at
+            /// the given baud rate.
+            ///
+            /// The panic handler reinitializes the USART from scratch, so it
+            /// will still work if the USART has since been reconfigured or
+            /// deactivated. This is available only with the `panic-usart`
+            /// crate feature.
+            pub fn use_for_panics(
+                &self,
+                usart_clock: &clock::UsartClock,
+                baud: clock::BaudRate,
+                action: panic::Action,
+            ) {
+                panic::register($index, TXD::NUMBER, usart_clock, baud, action);
+            }
+        }
+
+        impl<TXD, RXD, RTS, CTS, SCLK>
+            $typename<mode::Asynchronous, TXD, pins::mode::Assigned<RXD>, RTS, CTS, SCLK>
+        where
+            TXD: pins::PinAssignment,
+            RXD: pins::Pin,
+            RTS: pins::PinAssignment,
+            CTS: pins::PinAssignment,
+            SCLK: pins::PinAssignment,
+        {
+            /// Waits for a 0x55 synchronization character (an ASCII `U`) on
+            /// RXD, measures its bit timing, and changes the baud rate to
+            /// match it.
+            ///
+            /// The LPC81x USART has no hardware autobaud function, so this
+            /// measures the character in software by polling RXD and timing
+            /// it with SysTick, which is reconfigured for the purpose. It
+            /// blocks until a synchronization character arrives, and works
+            /// best with interrupts disabled. The synchronization character
+            /// itself is discarded.
+            ///
+            /// Returns the new baud rate, or `None` if the measured rate
+            /// cannot be produced from the USART clock, in which case the
+            /// baud rate is unchanged.
+            pub fn autobaud(
+                &mut self,
+                usart_clock: &clock::UsartClock,
+                clocks: &crate::clock::Clocks,
+                syst: &mut cortex_m::peripheral::SYST,
+            ) -> Option<clock::BaudRate> {
+                let cycles = measure_sync(RXD::NUMBER, syst);
+                if cycles < SYNC_BIT_TIMES {
+                    return None;
+                }
+                let bit_cycles = cycles / SYNC_BIT_TIMES;
+                // Let the USART finish receiving the character at whatever
+                // rate it was using, and then discard it.
+                cortex_m::asm::delay(bit_cycles * 2);
+                let periph = lpc81x_pac::$typename::ptr();
+                unsafe {
+                    (*periph).rxdata.read();
+                    (*periph).stat.write(|w| {
+                        w.overrunint()
+                            .set_bit()
+                            .framerrint()
+                            .set_bit()
+                            .parityerrint()
+                            .set_bit()
+                            .rxnoiseint()
+                            .set_bit()
+                    });
+                }
+                let measured = ((clocks.system_clock() as u64) * (SYNC_BIT_TIMES as u64)
+                    / (cycles as u64)) as u32;
+                let rate = usart_clock.baud_rate(measured)?;
+                Self::set_brg(rate);
+                Some(rate)
+            }
+        }
+
+        impl<TXD, RXD, RTS, CTS, SCLK> $typename<mode::Asynchronous, TXD, RXD, RTS, CTS, SCLK>
+        where
+            TXD: pins::PinAssignment,
+            RXD: pins::PinAssignment,
+            RTS: pins::PinAssignment,
+            CTS: pins::PinAssignment,
+            SCLK: pins::PinAssignment,
+        {
+            /// Consumes the USART and returns it in RS-485 mode, driving the
+            /// given output pin as the transceiver's driver-enable signal.
+            ///
+            /// The clocks and the baud rate the USART was activated with are
+            /// used to calculate the turnaround delay. Use
+            /// `rs485::Rs485::release` to recover the USART object and the
+            /// pin.
+            pub fn into_rs485<DE>(
+                self,
+                de: DE,
+                config: rs485::Config,
+                clocks: &crate::clock::Clocks,
+                baud: clock::BaudRate,
+            ) -> rs485::Rs485<Self, DE>
+            where
+                DE: embedded_hal::digital::v2::OutputPin<Error = !>,
+            {
+                rs485::Rs485::new(self, de, config, clocks, baud)
+            }
+        }
+
+        unsafe impl<MODE, TXD, RXD, RTS, CTS, SCLK> Instance
+            for $typename<MODE, TXD, RXD, RTS, CTS, SCLK>
+        where
+            MODE: Mode,
+            TXD: pins::PinAssignment,
+            RXD: pins::PinAssignment,
+            RTS: pins::PinAssignment,
+            CTS: pins::PinAssignment,
+            SCLK: pins::PinAssignment,
+        {
+            const NVIC_BITMASK: u32 = 1 << $irq;
+
+            #[inline(always)]
+            fn registers() -> *const lpc81x_pac::usart0::RegisterBlock {
+                lpc81x_pac::$typename::ptr()
+            }
+
+            #[inline(always)]
+            fn state() -> &'static buffered::State {
+                // A static inside a generic function is shared by all of its
+                // instantiations, so this is one state per USART.
+                static STATE: buffered::State = buffered::State::new();
+                &STATE
+            }
+        }
+
+        impl<MODE: mode::Active>
+            $typename<
+                MODE,
+                pins::mode::Unassigned,
+                pins::mode::Unassigned,
+                pins::mode::Unassigned,
+                pins::mode::Unassigned,
+                pins::mode::Unassigned,
+            >
+        {
+            /// Consumes the active USART and returns it deactivated.
+            ///
+            /// This method can be called only when all assigned pins have
+            /// already been released. For example:
+            ///
+            /// ```rust
+            /// let (serial, txd) = serial.release_txd();
+            /// let (serial, rxd) = serial.release_rxd();
+            /// let serial = serial.deactivate();
+            /// ```
+            pub fn deactivate(
+                self,
+            ) -> $typename<
+                mode::Inactive,
+                pins::mode::Unassigned,
+                pins::mode::Unassigned,
+                pins::mode::Unassigned,
+                pins::mode::Unassigned,
+                pins::mode::Unassigned,
+            > {
+                Self::set_enabled(false, Operation::Asynchronous, cfg::RESET_CONFIG);
+                Self::set_usart_clock(false);
+                $typename::new()
+            }
+        }
+
+        impl<W, MODE, TXD, RXD, RTS, CTS, SCLK> embedded_hal::serial::Read<W>
+            for $typename<MODE, TXD, RXD, RTS, CTS, SCLK>
+        where
+            W: word::Word,
+            MODE: mode::Active,
+            TXD: pins::PinAssignment,
+            RXD: pins::PinAssignment,
+            RTS: pins::PinAssignment,
+            CTS: pins::PinAssignment,
+            SCLK: pins::PinAssignment,
+        {
+            type Error = Error;
+
+            /// Reads a received character, if one is available.
+            ///
+            /// If a receive error is reported, the character that caused it
+            /// is discarded. With nine data bits, read `u16` words to
+            /// receive the ninth bit.
+            fn read(&mut self) -> nb::Result<W, Error> {
+                let periph = lpc81x_pac::$typename::ptr();
+                let stat = unsafe { (*periph).stat.read() };
+                if stat.overrunint().bit_is_set() {
+                    unsafe { (*periph).stat.write(|w| w.overrunint().set_bit()) };
+                    return Err(nb::Error::Other(Error::Overrun));
+                }
+                if stat.rxrdy().bit_is_clear() {
+                    return Err(nb::Error::WouldBlock);
+                }
+                let raw = unsafe { (*periph).rxdatastat.read() };
+                if raw.framerr().bit_is_set() {
+                    unsafe { (*periph).stat.write(|w| w.framerrint().set_bit()) };
+                    return Err(nb::Error::Other(Error::Framing));
+                }
+                if raw.parityerr().bit_is_set() {
+                    unsafe { (*periph).stat.write(|w| w.parityerrint().set_bit()) };
+                    return Err(nb::Error::Other(Error::Parity));
+                }
+                if raw.rxnoise().bit_is_set() {
+                    unsafe { (*periph).stat.write(|w| w.rxnoiseint().set_bit()) };
+                    return Err(nb::Error::Other(Error::Noise));
+                }
+                Ok(W::from_received(raw.rxdat().bits()))
+            }
+        }
+
+        impl<W, MODE, TXD, RXD, RTS, CTS, SCLK> embedded_hal::serial::Write<W>
+            for $typename<MODE, TXD, RXD, RTS, CTS, SCLK>
+        where
+            W: word::Word,
+            MODE: mode::Active,
+            TXD: pins::PinAssignment,
+            RXD: pins::PinAssignment,
+            RTS: pins::PinAssignment,
+            CTS: pins::PinAssignment,
+            SCLK: pins::PinAssignment,
+        {
+            type Error = !;
+
+            /// Writes a character to the transmit buffer, if it is empty.
+            ///
+            /// With nine data bits, write `u16` words to set the ninth bit.
+            fn write(&mut self, word: W) -> nb::Result<(), !> {
+                let periph = lpc81x_pac::$typename::ptr();
+                let stat = unsafe { (*periph).stat.read() };
+                if stat.txrdy().bit_is_clear() {
+                    return Err(nb::Error::WouldBlock);
+                }
+                let raw = word.value_to_transmit();
+                unsafe { (*periph).txdata.write(|w| w.txdat().bits(raw)) };
+                Ok(())
+            }
+
+            /// Waits until all written characters have been transmitted.
+            fn flush(&mut self) -> nb::Result<(), !> {
+                let periph = lpc81x_pac::$typename::ptr();
+                let stat = unsafe { (*periph).stat.read() };
+                if stat.txidle().bit_is_clear() {
+                    return Err(nb::Error::WouldBlock);
+                }
+                Ok(())
+            }
+        }
+
+        impl<W, MODE, TXD, RXD, RTS, CTS, SCLK> embedded_hal::blocking::serial::write::Default<W>
+            for $typename<MODE, TXD, RXD, RTS, CTS, SCLK>
+        where
+            W: word::Word,
+            MODE: mode::Active,
+            TXD: pins::PinAssignment,
+            RXD: pins::PinAssignment,
+            RTS: pins::PinAssignment,
+            CTS: pins::PinAssignment,
+            SCLK: pins::PinAssignment,
+        {
+        }
+    };
+}
+
+usart_device!(USART0, usart0, {
+    INDEX: 0,
+    INTERRUPT: 3,
+    WAKEUP: wakeup::USART0,
+    RESETCTRL: usart0_rst_n,
+    CLKCTRL: uart0,
+    TXD: (pinassign0, u0_txd_o),
+    RXD: (pinassign0, u0_rxd_i),
+    RTS: (pinassign0, u0_rts_o),
+    CTS: (pinassign0, u0_cts_i),
+    SCLK: (pinassign1, u0_sclk_io)
+});
+usart_device!(USART1, usart1, {
+    INDEX: 1,
+    INTERRUPT: 4,
+    WAKEUP: wakeup::USART1,
+    RESETCTRL: uart1_rst_n,
+    CLKCTRL: uart1,
+    TXD: (pinassign1, u1_txd_o),
+    RXD: (pinassign1, u1_rxd_i),
+    RTS: (pinassign1, u1_rts_o),
+    CTS: (pinassign2, u1_cts_i),
+    SCLK: (pinassign2, u1_sclk_io)
+});
+usart_device!(USART2, usart2, {
+    INDEX: 2,
+    INTERRUPT: 5,
+    WAKEUP: wakeup::USART2,
+    RESETCTRL: uart2_rst_n,
+    CLKCTRL: uart2,
+    TXD: (pinassign2, u2_txd_o),
+    RXD: (pinassign2, u2_rxd_i),
+    RTS: (pinassign3, u2_rts_o),
+    CTS: (pinassign3, u2_cts_i),
+    SCLK: (pinassign3, u2_sclk_io)
+});
+
+/// Selects the edge of SCLK on which received data is sampled in the
+/// synchronous modes.
+///
+/// Transmitted data changes on the opposite edge.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SampleEdge {
+    Falling,
+    Rising,
+}
+
+// The operating modes that can be selected in the CFG register.
+#[derive(Clone, Copy)]
+enum Operation {
+    Asynchronous,
+    SynchronousMaster(SampleEdge),
+    SynchronousSlave(SampleEdge),
+}
+
+/// Errors that can be reported when reading from a USART.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// A character was received before the previous one was read, so the
+    /// previous one was lost.
+    Overrun,
+
+    /// A character was received without a valid stop bit.
+    Framing,
+
+    /// A character was received with an incorrect parity bit.
+    Parity,
+
+    /// The receiver detected noise while sampling a character.
+    Noise,
+}
+
+// Implemented by the USART types so that the wrapper objects in the
+// `buffered` and `rs485` modules can find the registers and state of the
+// USART they wrap.
+//
+// Can be safely implemented only by types in this crate.
+pub unsafe trait Instance {
+    const NVIC_BITMASK: u32;
+
+    fn registers() -> *const lpc81x_pac::usart0::RegisterBlock;
+
+    fn state() -> &'static buffered::State;
+}
+
+// Represents USART modes.
+//
+// Can be safely implemented only by types in this crate.
+pub unsafe trait Mode {}
+
+// A 0x55 character is transmitted as alternating low and high bits, with the
+// fifth rising edge at the start of the stop bit, nine bit times after the
+// falling edge at the start of the start bit.
+const SYNC_BIT_TIMES: u32 = 9;
+
+// Waits for a 0x55 character on the given pin and returns its length up to
+// the start of the stop bit, in system clock cycles.
+fn measure_sync(pin: u8, syst: &mut cortex_m::peripheral::SYST) -> u32 {
+    use cortex_m::peripheral::{syst::SystClkSource, SYST};
+
+    let gpio = lpc81x_pac::GPIO_PORT::ptr();
+    let is_high = || unsafe { (*gpio).b[pin as usize].read().bits() != 0 };
+
+    syst.disable_interrupt();
+    syst.set_clock_source(SystClkSource::Core);
+    syst.set_reload(0x00ff_ffff);
+    syst.clear_current();
+    syst.enable_counter();
+
+    // If we start in the middle of a break or a character, wait for the line
+    // to return to idle first.
+    while !is_high() {}
+    while is_high() {}
+    let start = SYST::get_current();
+    for i in 0..5 {
+        while !is_high() {}
+        if i < 4 {
+            while is_high() {}
+        }
+    }
+    let end = SYST::get_current();
+
+    // SysTick counts down, and wraps within 24 bits.
+    start.wrapping_sub(end) & 0x00ff_ffff
+}
+
+#[inline(always)]
+fn unused<T>(_v: T) {}
+
+// Helper function for creating "instances" of our zero-length pin types
+// without needing to state their names, when we're releasing/deactivating
+// pins.
+#[inline(always)]
+fn pin_type_as_is<T: pins::Pin>() -> T {
+    // This is safe because our pin types are zero-length anyway, and so
+    // "filling them with zeroes" is indistinguishable from properly
+    // initializing them.
+    unsafe { core::mem::zeroed() }
+}
@@ -0,0 +1,21 @@
+pub enum Inactive {}
+unsafe impl super::Mode for Inactive {}
+
+pub enum Asynchronous {}
+unsafe impl super::Mode for Asynchronous {}
+
+pub enum SynchronousMaster {}
+unsafe impl super::Mode for SynchronousMaster {}
+
+pub enum SynchronousSlave {}
+unsafe impl super::Mode for SynchronousSlave {}
+
+pub unsafe trait Active: super::Mode {}
+unsafe impl Active for Asynchronous {}
+unsafe impl Active for SynchronousMaster {}
+unsafe impl Active for SynchronousSlave {}
+
+// Implemented by the synchronous modes, which use the SCLK signal.
+pub unsafe trait Synchronous: Active {}
+unsafe impl Synchronous for SynchronousMaster {}
+unsafe impl Synchronous for SynchronousSlave {}
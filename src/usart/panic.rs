@@ -0,0 +1,198 @@
+//! A panic handler that reports panics on a USART.
+//!
+//! This module is available only when the `panic-usart` crate feature is
+//! enabled, in which case this crate provides the application's panic
+//! handler. The USART to use is selected at runtime by calling
+//! `use_for_panics` on an active USART with an assigned TXD pin:
+//!
+//! ```rust
+//! let baud = usart_clock.baud_rate(115_200).unwrap();
+//! serial.use_for_panics(&usart_clock, baud, hal::usart::panic::Action::Reset);
+//! ```
+//!
+//! On panic, the handler disables interrupts, resets the selected USART and
+//! reconfigures it from scratch with eight data bits, no parity and one stop
+//! bit, so that it works regardless of what state the application left it
+//! in. It then reports the panic and either halts or resets the device.
+//! Panics before `use_for_panics` is called just halt.
+//!
+//! By default, only the source location of the panic is reported, which
+//! avoids pulling the `core::fmt` formatting machinery into the program.
+//! Enable the `panic-usart-message` feature as well to also report the
+//! panic message, at the cost of the extra flash that formatting requires.
+
+use super::clock;
+use super::console::Console;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// What the panic handler does after reporting the panic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Loops forever, which allows a debugger to inspect the state.
+    Halt,
+
+    /// Resets the device.
+    Reset,
+}
+
+// The USART to use, packed as the USART index plus one in bits 0-7 (or zero
+// if none has been selected), the TXD pin number in bits 8-15 and the action
+// in bit 16.
+static TARGET: AtomicU32 = AtomicU32::new(0);
+
+// The USART clock dividers and baud rate, packed as UARTCLKDIV in bits 0-7,
+// UARTFRGMULT in bits 8-15 and BRGVAL in bits 16-31.
+static TIMING: AtomicU32 = AtomicU32::new(0);
+
+pub(crate) fn register(
+    index: u8,
+    txd: u8,
+    usart_clock: &clock::UsartClock,
+    baud: clock::BaudRate,
+    action: Action,
+) {
+    let (clk_div, frg_mult) = usart_clock.dividers();
+    TIMING.store(
+        (clk_div as u32) | ((frg_mult as u32) << 8) | ((baud.brg_val() as u32) << 16),
+        Ordering::Relaxed,
+    );
+    TARGET.store(
+        ((index as u32) + 1) | ((txd as u32) << 8) | ((action == Action::Reset) as u32) << 16,
+        Ordering::Release,
+    );
+}
+
+#[panic_handler]
+fn panic(info: &core::panic::PanicInfo) -> ! {
+    cortex_m::interrupt::disable();
+
+    let target = TARGET.load(Ordering::Acquire);
+    if target & 0xff != 0 {
+        let index = (target & 0xff) as u8 - 1;
+        let txd = (target >> 8) as u8;
+        let regs = reinitialize(index, txd, TIMING.load(Ordering::Relaxed));
+        let mut console = Console::new(PanicTx(regs));
+        let _ = report(&mut console, info);
+        let _ = console.flush();
+
+        if target & (1 << 16) != 0 {
+            cortex_m::peripheral::SCB::sys_reset();
+        }
+    }
+
+    loop {
+        core::sync::atomic::compiler_fence(Ordering::SeqCst);
+    }
+}
+
+#[cfg(feature = "panic-usart-message")]
+fn report(console: &mut Console<PanicTx>, info: &core::panic::PanicInfo) -> core::fmt::Result {
+    use core::fmt::Write;
+    write!(console, "\n{}\n", info)
+}
+
+#[cfg(not(feature = "panic-usart-message"))]
+fn report(console: &mut Console<PanicTx>, info: &core::panic::PanicInfo) -> core::fmt::Result {
+    use core::fmt::Write;
+    console.write_str("\npanicked")?;
+    if let Some(location) = info.location() {
+        console.write_str(" at ")?;
+        console.write_str(location.file())?;
+        console.write_str(":")?;
+        console.write_u32(location.line())?;
+    }
+    console.write_str("\n")
+}
+
+// Puts the given USART back into a known state, regardless of what the
+// application had done with it, and returns its registers.
+fn reinitialize(index: u8, txd: u8, timing: u32) -> *const lpc81x_pac::usart0::RegisterBlock {
+    let syscon = lpc81x_pac::SYSCON::ptr();
+    let swm = lpc81x_pac::SWM::ptr();
+    unsafe {
+        let regs = match index {
+            0 => {
+                (*syscon)
+                    .presetctrl
+                    .modify(|_, w| w.usart0_rst_n().bit(false));
+                (*syscon)
+                    .presetctrl
+                    .modify(|_, w| w.usart0_rst_n().bit(true));
+                (*syscon).sysahbclkctrl.modify(|_, w| w.uart0().enable());
+                (*swm).pinassign0.modify(|_, w| w.u0_txd_o().bits(txd));
+                lpc81x_pac::USART0::ptr()
+            }
+            1 => {
+                (*syscon)
+                    .presetctrl
+                    .modify(|_, w| w.uart1_rst_n().bit(false));
+                (*syscon)
+                    .presetctrl
+                    .modify(|_, w| w.uart1_rst_n().bit(true));
+                (*syscon).sysahbclkctrl.modify(|_, w| w.uart1().enable());
+                (*swm).pinassign1.modify(|_, w| w.u1_txd_o().bits(txd));
+                lpc81x_pac::USART1::ptr()
+            }
+            _ => {
+                (*syscon)
+                    .presetctrl
+                    .modify(|_, w| w.uart2_rst_n().bit(false));
+                (*syscon)
+                    .presetctrl
+                    .modify(|_, w| w.uart2_rst_n().bit(true));
+                (*syscon).sysahbclkctrl.modify(|_, w| w.uart2().enable());
+                (*swm).pinassign2.modify(|_, w| w.u2_txd_o().bits(txd));
+                lpc81x_pac::USART2::ptr()
+            }
+        };
+        (*syscon)
+            .presetctrl
+            .modify(|_, w| w.uartfrg_rst_n().bit(true));
+        (*syscon).uartfrgdiv.write(|w| w.div().bits(0xff));
+        (*syscon)
+            .uartfrgmult
+            .write(|w| w.mult().bits((timing >> 8) as u8));
+        (*syscon).uartclkdiv.write(|w| w.div().bits(timing as u8));
+        (*regs)
+            .brg
+            .write(|w| w.brgval().bits((timing >> 16) as u16));
+        (*regs).cfg.write(|w| {
+            w.enable()
+                .set_bit()
+                .datalen()
+                ._8_bit_data_length_()
+                .paritysel()
+                .no_parity_()
+                .stoplen()
+                ._1_stop_bit_()
+        });
+        regs
+    }
+}
+
+// A minimal transmitter for the reinitialized USART, so that the panic
+// handler doesn't depend on the state of the application's USART object.
+struct PanicTx(*const lpc81x_pac::usart0::RegisterBlock);
+
+impl embedded_hal::serial::Write<u8> for PanicTx {
+    type Error = !;
+
+    fn write(&mut self, word: u8) -> nb::Result<(), !> {
+        let stat = unsafe { (*self.0).stat.read() };
+        if stat.txrdy().bit_is_clear() {
+            return Err(nb::Error::WouldBlock);
+        }
+        unsafe { (*self.0).txdata.write(|w| w.txdat().bits(word as u16)) };
+        Ok(())
+    }
+
+    fn flush(&mut self) -> nb::Result<(), !> {
+        let stat = unsafe { (*self.0).stat.read() };
+        if stat.txidle().bit_is_clear() {
+            return Err(nb::Error::WouldBlock);
+        }
+        Ok(())
+    }
+}
+
+impl embedded_hal::blocking::serial::write::Default<u8> for PanicTx {}
@@ -0,0 +1,271 @@
+//! RS-485 half-duplex operation with driver-enable control.
+//!
+//! An RS-485 transceiver's driver-enable (DE) input must be asserted while
+//! the USART is transmitting and released promptly afterwards so that other
+//! nodes can use the bus. Unlike some later LPC800 parts, the LPC81x USART
+//! has no automatic output-enable function on its RTS signal, so `Rs485`
+//! instead drives DE using a digital output pin: it asserts DE before the
+//! first character written and releases it when `flush` finds that the
+//! USART has finished transmitting, after an optional turnaround delay.
+//!
+//! ```rust
+//! let de = pins.gpio14.to_digital_output(false);
+//! let config = hal::usart::rs485::Config::default().address(0x12);
+//! let baud = usart_clock.baud_rate(115_200).unwrap();
+//! let mut bus = serial.into_rs485(de, config, &clocks, baud);
+//! bus.write_address(0x34).unwrap();
+//! bus.bwrite_all(b"hello").unwrap();
+//! bus.bflush().unwrap();
+//! ```
+//!
+//! When a node address is configured, the USART uses nine data bits and the
+//! address-detect mode for multidrop buses: characters with the ninth bit
+//! set are addresses, and the receiver ignores all other characters until
+//! it sees its own address.
+
+use super::{clock, Error, Instance};
+use crate::clock::Clocks;
+use embedded_hal::digital::v2::OutputPin;
+
+/// Configuration for RS-485 operation.
+#[derive(Clone, Copy, Debug)]
+pub struct Config {
+    de_active_high: bool,
+    turnaround_bits: u8,
+    address: Option<u8>,
+}
+
+impl Config {
+    /// Selects whether DE is asserted by driving the pin high (the default)
+    /// or low.
+    pub fn de_active_high(mut self, active_high: bool) -> Self {
+        self.de_active_high = active_high;
+        self
+    }
+
+    /// Sets the number of bit times to keep DE asserted after the end of the
+    /// last stop bit. The default is zero.
+    pub fn turnaround_bits(mut self, bits: u8) -> Self {
+        self.turnaround_bits = bits;
+        self
+    }
+
+    /// Enables nine-bit multidrop addressing, with the given node address.
+    pub fn address(mut self, address: u8) -> Self {
+        self.address = Some(address);
+        self
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            de_active_high: true,
+            turnaround_bits: 0,
+            address: None,
+        }
+    }
+}
+
+/// A USART in RS-485 mode, along with the pin that drives the
+/// transceiver's DE input.
+pub struct Rs485<U: Instance, DE: OutputPin<Error = !>> {
+    usart: U,
+    de: DE,
+    de_active_high: bool,
+    turnaround_cycles: u32,
+    address: Option<u8>,
+    transmitting: bool,
+    saved_cfg: u32,
+}
+
+/// An RS-485 object represents access to a single system peripheral, so it's
+/// not safe to share it across multiple threads without some external
+/// concurrency control mechanisms.
+impl<U: Instance, DE: OutputPin<Error = !>> !Sync for Rs485<U, DE> {}
+
+impl<U: Instance, DE: OutputPin<Error = !>> Rs485<U, DE> {
+    pub(crate) fn new(
+        usart: U,
+        de: DE,
+        config: Config,
+        clocks: &Clocks,
+        baud: clock::BaudRate,
+    ) -> Self {
+        let turnaround_cycles = if config.turnaround_bits == 0 {
+            0
+        } else {
+            ((clocks.system_clock() as u64) * (config.turnaround_bits as u64)
+                / (baud.actual() as u64)) as u32
+        };
+        let mut ret = Self {
+            usart,
+            de,
+            de_active_high: config.de_active_high,
+            turnaround_cycles,
+            address: config.address,
+            transmitting: false,
+            saved_cfg: unsafe { (*U::registers()).cfg.read().bits() },
+        };
+        ret.set_de(false);
+        if config.address.is_some() {
+            set_address_mode::<U>();
+        }
+        ret
+    }
+
+    /// Returns the node address, if multidrop addressing is enabled.
+    pub fn address(&self) -> Option<u8> {
+        self.address
+    }
+
+    /// Writes an address character, with the ninth bit set, to select the
+    /// node that should receive the characters that follow.
+    ///
+    /// This is available only when multidrop addressing is enabled, and
+    /// will panic otherwise.
+    pub fn write_address(&mut self, address: u8) -> nb::Result<(), !> {
+        assert!(self.address.is_some());
+        self.write_raw(0x100 | (address as u16))
+    }
+
+    /// Stops RS-485 operation and returns the underlying USART object and
+    /// the DE pin, with DE released and the USART's original character
+    /// format restored.
+    ///
+    /// Any character still being transmitted may be cut short, so callers
+    /// should normally call `flush` first.
+    pub fn release(mut self) -> (U, DE) {
+        self.set_de(false);
+        if self.address.is_some() {
+            let regs = U::registers();
+            let saved = self.saved_cfg;
+            unsafe {
+                (*regs).cfg.modify(|_, w| w.enable().clear_bit());
+                (*regs).cfg.write(|w| w.bits(saved));
+            }
+            set_address_detect::<U>(false);
+        }
+        (self.usart, self.de)
+    }
+
+    fn set_de(&mut self, asserted: bool) {
+        // The pin's error type is `!`, so there's nothing to handle here.
+        let _ = if asserted == self.de_active_high {
+            self.de.set_high()
+        } else {
+            self.de.set_low()
+        };
+    }
+
+    fn write_raw(&mut self, word: u16) -> nb::Result<(), !> {
+        let regs = U::registers();
+        if !self.transmitting {
+            self.set_de(true);
+            self.transmitting = true;
+        }
+        let stat = unsafe { (*regs).stat.read() };
+        if stat.txrdy().bit_is_clear() {
+            return Err(nb::Error::WouldBlock);
+        }
+        unsafe { (*regs).txdata.write(|w| w.txdat().bits(word)) };
+        Ok(())
+    }
+}
+
+impl<U: Instance, DE: OutputPin<Error = !>> embedded_hal::serial::Write<u8> for Rs485<U, DE> {
+    type Error = !;
+
+    /// Writes a character to the transmit buffer, asserting DE first if it
+    /// is not already asserted.
+    fn write(&mut self, word: u8) -> nb::Result<(), !> {
+        self.write_raw(word as u16)
+    }
+
+    /// Waits until all written characters have been transmitted and then,
+    /// after the turnaround delay, releases DE.
+    fn flush(&mut self) -> nb::Result<(), !> {
+        let regs = U::registers();
+        let stat = unsafe { (*regs).stat.read() };
+        if stat.txidle().bit_is_clear() {
+            return Err(nb::Error::WouldBlock);
+        }
+        if self.transmitting {
+            if self.turnaround_cycles > 0 {
+                cortex_m::asm::delay(self.turnaround_cycles);
+            }
+            self.set_de(false);
+            self.transmitting = false;
+        }
+        Ok(())
+    }
+}
+
+impl<U: Instance, DE: OutputPin<Error = !>> embedded_hal::blocking::serial::write::Default<u8>
+    for Rs485<U, DE>
+{
+}
+
+impl<U: Instance, DE: OutputPin<Error = !>> embedded_hal::serial::Read<u8> for Rs485<U, DE> {
+    type Error = Error;
+
+    /// Reads a received character, if one is available.
+    ///
+    /// When multidrop addressing is enabled, address characters are
+    /// consumed here rather than returned: the receiver starts accepting
+    /// data after its own address and goes back to ignoring data after any
+    /// other address.
+    fn read(&mut self) -> nb::Result<u8, Error> {
+        let regs = U::registers();
+        let stat = unsafe { (*regs).stat.read() };
+        if stat.overrunint().bit_is_set() {
+            unsafe { (*regs).stat.write(|w| w.overrunint().set_bit()) };
+            return Err(nb::Error::Other(Error::Overrun));
+        }
+        if stat.rxrdy().bit_is_clear() {
+            return Err(nb::Error::WouldBlock);
+        }
+        let raw = unsafe { (*regs).rxdatastat.read() };
+        if raw.framerr().bit_is_set() {
+            unsafe { (*regs).stat.write(|w| w.framerrint().set_bit()) };
+            return Err(nb::Error::Other(Error::Framing));
+        }
+        if raw.parityerr().bit_is_set() {
+            unsafe { (*regs).stat.write(|w| w.parityerrint().set_bit()) };
+            return Err(nb::Error::Other(Error::Parity));
+        }
+        if raw.rxnoise().bit_is_set() {
+            unsafe { (*regs).stat.write(|w| w.rxnoiseint().set_bit()) };
+            return Err(nb::Error::Other(Error::Noise));
+        }
+        let word = raw.rxdat().bits();
+        if let Some(address) = self.address {
+            if word & 0x100 != 0 {
+                set_address_detect::<U>(word as u8 != address);
+                return Err(nb::Error::WouldBlock);
+            }
+        }
+        Ok(word as u8)
+    }
+}
+
+// Switches to nine data bits with address detection. The USART must be
+// disabled while its configuration changes.
+fn set_address_mode<U: Instance>() {
+    let regs = U::registers();
+    unsafe {
+        (*regs).cfg.modify(|_, w| w.enable().clear_bit());
+        (*regs)
+            .cfg
+            .modify(|_, w| w.datalen()._9_bit_data_length_t());
+        (*regs).cfg.modify(|_, w| w.enable().set_bit());
+    }
+    set_address_detect::<U>(true);
+}
+
+// While address detection is enabled, the receiver ignores characters that
+// don't have the ninth bit set.
+fn set_address_detect<U: Instance>(enabled: bool) {
+    let regs = U::registers();
+    unsafe { (*regs).ctrl.modify(|_, w| w.addrdet().bit(enabled)) };
+}
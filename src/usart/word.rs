@@ -0,0 +1,28 @@
+// Represents the word types that can be transmitted and received by the
+// USART, with `u16` needed for nine data bits.
+//
+// Can be safely implemented only by types in this crate.
+pub unsafe trait Word {
+    fn value_to_transmit(&self) -> u16;
+    fn from_received(raw: u16) -> Self;
+}
+
+unsafe impl Word for u8 {
+    fn value_to_transmit(&self) -> u16 {
+        *self as u16
+    }
+
+    fn from_received(raw: u16) -> Self {
+        raw as u8
+    }
+}
+
+unsafe impl Word for u16 {
+    fn value_to_transmit(&self) -> u16 {
+        *self
+    }
+
+    fn from_received(raw: u16) -> Self {
+        raw
+    }
+}
@@ -0,0 +1,45 @@
+// Helpers for configuring the start logic that selects which interrupts can
+// wake the device from deep-sleep and power-down modes.
+//
+// The drivers for each wake-capable peripheral expose this as
+// `enable_wakeup` and `disable_wakeup` methods, which call into these.
+
+// Bit positions in STARTERP1 for the peripheral wake-up sources. The PAC
+// only models some of these, so we write the raw bits for all of them.
+pub(crate) const SPI0: u8 = 0;
+pub(crate) const SPI1: u8 = 1;
+pub(crate) const USART0: u8 = 3;
+pub(crate) const USART1: u8 = 4;
+pub(crate) const USART2: u8 = 5;
+pub(crate) const I2C: u8 = 8;
+pub(crate) const BOD: u8 = 13;
+
+// Enables or disables one of the pin interrupts as a wake-up source.
+pub(crate) fn set_pin_interrupt(idx: u8, enabled: bool) {
+    let syscon = lpc81x_pac::SYSCON::ptr();
+    unsafe {
+        (*syscon)
+            .starterp0
+            .modify(|r, w| w.bits(with_bit(r.bits(), idx, enabled)))
+    }
+}
+
+// Enables or disables one of the peripheral interrupts as a wake-up source,
+// using one of the bit position constants above.
+pub(crate) fn set_peripheral(bit: u8, enabled: bool) {
+    let syscon = lpc81x_pac::SYSCON::ptr();
+    unsafe {
+        (*syscon)
+            .starterp1
+            .modify(|r, w| w.bits(with_bit(r.bits(), bit, enabled)))
+    }
+}
+
+#[inline(always)]
+fn with_bit(v: u32, bit: u8, set: bool) -> u32 {
+    if set {
+        v | (1 << bit)
+    } else {
+        v & !(1 << bit)
+    }
+}